@@ -1,19 +1,77 @@
 use eframe::egui;
 use crate::highlight;
 use crate::search;
-use egui::{text::LayoutJob, RichText, TextureHandle};
+use egui::{text::LayoutJob, ColorImage, RichText, TextureHandle};
+use std::collections::HashMap;
 use std::fs;
-use rfd::FileDialog;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use crate::session::Session;
+use crate::view_state::{ViewDefaults, ViewState};
 
 const MAX_FILE_SIZE_BYTES: u64 = 10_000_000; // 10MB
+// Images are downsampled after decoding, so allow much larger files
+const MAX_IMAGE_FILE_SIZE_BYTES: u64 = 500_000_000; // 500MB
 const MAX_RECENT_FILES: usize = 10;
 const BIG_TEXT_CHAR_THRESHOLD: usize = 500_000; // Disable heavy features beyond this
 pub(crate) const HIGHLIGHT_CHAR_THRESHOLD: usize = 200_000; // Disable syntax/mark highlights beyond this
+const MATCHED_BRACKET_BG: egui::Color32 = egui::Color32::from_rgba_premultiplied(90, 110, 140, 140);
+pub(crate) const UNBALANCED_BRACKET_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 60, 60);
 
 pub enum Content {
     Text(String),
-    Image(TextureHandle),
+    Image(Box<LoadedImage>),
+}
+
+/// A decoded image: the GPU texture plus the CPU frames kept for inspection and playback.
+/// Animations share one texture that is re-uploaded when the frame changes.
+pub struct LoadedImage {
+    pub(crate) texture: TextureHandle,
+    pub(crate) frames: Vec<crate::io::ImageFrame>,
+    pub(crate) current_frame: usize,
+    pub(crate) playing: bool,
+    // Time spent on the current frame, in seconds
+    pub(crate) frame_elapsed: f32,
+    pub(crate) frames_truncated: bool,
+    // View-only rotation/flip already applied to `frames`
+    pub(crate) transform: crate::transform::ViewTransform,
+    // Read on first display of the metadata panel
+    pub(crate) metadata: Option<crate::metadata::ImageMetadata>,
+    pub(crate) svg: Option<SvgSource>,
+    // Full-resolution size (before the view transform) when the texture is a downsampled preview
+    pub(crate) full_size: Option<[usize; 2]>,
+    pub(crate) tiles: Option<crate::tiles::TileSource>,
+}
+
+/// Vector source of an SVG image and the scale its texture was last rasterized at.
+pub struct SvgSource {
+    pub(crate) tree: resvg::usvg::Tree,
+    pub(crate) raster_scale: f32,
+}
+
+impl LoadedImage {
+    pub(crate) fn pixels(&self) -> &ColorImage {
+        &self.frames[self.current_frame].pixels
+    }
+
+    pub(crate) fn is_animated(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    /// Size in image units used for zoom and fit: the intrinsic SVG size
+    /// (independent of raster resolution), the full size of a downsampled
+    /// preview, or the texture size.
+    pub(crate) fn display_size(&self) -> egui::Vec2 {
+        let (w, h) = match (&self.svg, self.full_size) {
+            (Some(svg), _) => (svg.tree.size().width(), svg.tree.size().height()),
+            (None, Some([w, h])) => (w as f32, h as f32),
+            (None, None) => return self.texture.size_vec2(),
+        };
+        if self.transform.quarter_turns % 2 == 1 {
+            egui::vec2(h, w)
+        } else {
+            egui::vec2(w, h)
+        }
+    }
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -34,6 +92,95 @@ pub struct FileViewerApp {
     #[serde(skip)]
     pub(crate) show_about: bool,
     pub(crate) image_fit: bool,
+    // Session restore: reopen files, window geometry, panels and search on launch
+    pub(crate) restore_session: bool,
+    pub(crate) session: Session,
+    #[serde(skip)]
+    pub(crate) session_enabled: bool,
+    #[serde(skip)]
+    pub(crate) show_search_bar: bool,
+    #[serde(skip)]
+    pub(crate) show_status_extra: bool,
+    #[serde(skip)]
+    pub(crate) show_metadata: bool,
+    // Remembered view state per recent file
+    pub(crate) view_states: HashMap<PathBuf, ViewState>,
+    // Zoom, wrap and fit as saved in the settings; the fields above may hold a file's own values
+    #[serde(skip)]
+    pub(crate) view_defaults: ViewDefaults,
+    #[serde(skip)]
+    pub(crate) scroll_offset: egui::Vec2,
+    #[serde(skip)]
+    pub(crate) pending_scroll: Option<egui::Vec2>,
+    // Image canvas state: pan offset from center, animated zoom and its anchor
+    #[serde(skip)]
+    pub(crate) image_pan: egui::Vec2,
+    #[serde(skip)]
+    pub(crate) image_zoom_shown: f32,
+    #[serde(skip)]
+    pub(crate) image_zoom_anchor: Option<egui::Pos2>,
+    pub(crate) image_filter: crate::image_view::ImageFilter,
+    pub(crate) transparency_bg: crate::image_view::TransparencyBg,
+    pub(crate) transparency_custom: [u8; 3],
+    #[serde(skip)]
+    pub(crate) checker_texture: Option<TextureHandle>,
+    // Pixel inspector
+    pub(crate) show_loupe: bool,
+    // Load full-resolution tiles over downsampled previews when zoomed in
+    pub(crate) full_res_tiles: bool,
+    #[serde(skip)]
+    pub(crate) hovered_pixel: Option<crate::inspector::HoveredPixel>,
+    #[serde(skip)]
+    pub(crate) copied_color: Option<String>,
+    // Thumbnail grid of the current folder, shown instead of the viewer while open
+    #[serde(skip)]
+    pub(crate) gallery: Option<crate::gallery::Gallery>,
+    pub(crate) gallery_sort: crate::gallery::GallerySort,
+    // Second image for side-by-side, swipe, onion-skin and difference comparison
+    #[serde(skip)]
+    pub(crate) compare: Option<crate::compare::Compare>,
+    // Two-file text diff, shown instead of the viewer while open
+    #[serde(skip)]
+    pub(crate) text_diff: Option<crate::diff::TextDiff>,
+    // Branch and per-line changes against HEAD when the open file is in a git repo
+    #[serde(skip)]
+    pub(crate) git_status: Option<crate::git::FileStatus>,
+    // Git status being loaded for the given path
+    #[serde(skip)]
+    pub(crate) git_status_job: Option<(PathBuf, std::sync::mpsc::Receiver<Option<crate::git::FileStatus>>)>,
+    // Level coloring and filtering for log files, shown instead of the plain text view
+    #[serde(skip)]
+    pub(crate) log: Option<crate::log_view::LogView>,
+    // "Find in folder" side panel, open while Some
+    #[serde(skip)]
+    pub(crate) folder_search: Option<crate::folder_search::FolderSearch>,
+    // Symbol outline side panel for source, Markdown and JSON files
+    pub(crate) show_outline: bool,
+    #[serde(skip)]
+    pub(crate) outline: Option<crate::outline::Outline>,
+    // Line at the middle of the text view, followed by the outline
+    #[serde(skip)]
+    pub(crate) text_view_line: usize,
+    // Overview ruler of match, change and error marks beside the text, optionally with a minimap
+    pub(crate) show_overview: bool,
+    pub(crate) show_minimap: bool,
+    #[serde(skip)]
+    pub(crate) overview: Option<crate::overview::Overview>,
+    // Blame column for text files in a git repo
+    pub(crate) show_blame: bool,
+    #[serde(skip)]
+    pub(crate) blame: crate::blame::BlameCache,
+    // Prev/Next ordering; the listing is cached until the folder or options change
+    pub(crate) neighbor_sort: crate::listing::NeighborSort,
+    pub(crate) neighbor_recursive: bool,
+    #[serde(skip)]
+    pub(crate) dir_listing: Option<crate::listing::DirListing>,
+    // Fullscreen slideshow; toolbar and status panels are hidden while it runs
+    #[serde(skip)]
+    pub(crate) slideshow: Option<crate::slideshow::Slideshow>,
+    pub(crate) slideshow_interval: f32,
+    pub(crate) slideshow_shuffle: bool,
+    pub(crate) slideshow_loop: bool,
     // Derived/runtime-only state for text rendering
     #[serde(skip)]
     pub(crate) text_is_big: bool,
@@ -50,33 +197,104 @@ pub struct FileViewerApp {
     pub(crate) search_count: usize,
     #[serde(skip)]
     pub(crate) search_current: usize,
+    // Match the text view last scrolled to, so it only scrolls again when the match changes
+    #[serde(skip)]
+    pub(crate) scrolled_match: Option<(String, usize)>,
+    // Line to bring into view in the full text view, e.g. after leaving the line filter
+    #[serde(skip)]
+    pub(crate) goto_line: Option<usize>,
+    // Code folding: regions of the open text and the start lines currently folded
+    #[serde(skip)]
+    pub(crate) fold_regions: Option<crate::folding::FoldRegions>,
+    #[serde(skip)]
+    pub(crate) folded: std::collections::BTreeSet<usize>,
+    // Bracket pairs of the open text and the position last clicked in it
+    #[serde(skip)]
+    pub(crate) brackets: Option<crate::brackets::Brackets>,
+    #[serde(skip)]
+    pub(crate) text_cursor: Option<crate::brackets::TextPos>,
+    // Bookmarked lines of the open text
+    #[serde(skip)]
+    pub(crate) bookmarks: std::collections::BTreeSet<usize>,
+    // "grep view": only lines matching the find query, with context and grep -v style inversion
+    #[serde(skip)]
+    pub(crate) filter_lines: bool,
+    pub(crate) filter_context: usize,
+    #[serde(skip)]
+    pub(crate) filter_invert: bool,
+    #[serde(skip)]
+    pub(crate) line_filter: Option<crate::line_filter::LineFilter>,
 }
 
 impl FileViewerApp {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        if let Some(storage) = cc.storage
-            && let Some(s) = storage.get_string(eframe::APP_KEY)
-            && let Ok(mut app) = serde_json::from_str::<FileViewerApp>(&s)
+    pub fn new(cc: &eframe::CreationContext<'_>, args: &crate::cli::CliArgs) -> Self {
+        let mut app = Self::load_saved(cc).unwrap_or_default();
+        app.session_enabled = app.restore_session && !args.no_session;
+        if app.session_enabled {
+            app.restore_session_state(&cc.egui_ctx);
+        }
+        if let Some((left, right)) = &args.diff {
+            crate::diff_view::open(&mut app, left, right);
+        }
+        app
+    }
+
+    fn load_saved(cc: &eframe::CreationContext<'_>) -> Option<Self> {
+        let mut app = cc
+            .storage
+            .and_then(|storage| storage.get_string(eframe::APP_KEY))
+            .and_then(|s| serde_json::from_str::<FileViewerApp>(&s).ok())
+            .or_else(crate::settings::load_settings_from_disk)?;
+        // ensure runtime-only fields are initialized
+        app.view_defaults = ViewDefaults {
+            text_zoom: app.text_zoom,
+            word_wrap: app.word_wrap,
+            image_zoom: app.image_zoom,
+            image_fit: app.image_fit,
+        };
+        app.text_is_big = false;
+        app.text_line_count = 0;
+        app.text_is_lossy = false;
+        app.search_query = String::new();
+        app.search_active = false;
+        app.search_count = 0;
+        Some(app)
+    }
+
+    fn restore_session_state(&mut self, ctx: &egui::Context) {
+        let session = self.session.clone();
+        self.show_search_bar = session.panels.search_bar;
+        self.show_status_extra = session.panels.status_extra;
+        self.show_metadata = session.panels.metadata;
+        if let Some(path) = session.files.first()
+            && path.is_file()
         {
-            // ensure runtime-only fields are initialized
-            app.text_is_big = false;
-            app.text_line_count = 0;
-            app.text_is_lossy = false;
-            app.search_query = String::new();
-            app.search_active = false;
-            app.search_count = 0;
-            return app;
-        }
-        if let Some(mut app) = crate::settings::load_settings_from_disk() {
-            app.text_is_big = false;
-            app.text_line_count = 0;
-            app.text_is_lossy = false;
-            app.search_query = String::new();
-            app.search_active = false;
-            app.search_count = 0;
-            return app;
-        }
-        Default::default()
+            self.load_file(path.clone(), ctx);
+        }
+        if !session.search_query.is_empty() {
+            self.search_query = session.search_query;
+            self.search_count = 0;
+            self.search_current = 0;
+            if let Some(Content::Text(text)) = &self.content
+                && text.len() <= HIGHLIGHT_CHAR_THRESHOLD
+            {
+                self.search_count = search::recompute_count(&self.search_query, text);
+                if session.search_current < self.search_count {
+                    self.search_current = session.search_current;
+                }
+            }
+        }
+    }
+
+    /// Record what is currently open so it is saved along with the settings.
+    pub(crate) fn capture_session(&mut self, ctx: &egui::Context) {
+        self.session.window.capture(ctx);
+        self.session.files = self.current_path.iter().cloned().collect();
+        self.session.panels.search_bar = self.show_search_bar;
+        self.session.panels.status_extra = self.show_status_extra;
+        self.session.panels.metadata = self.show_metadata;
+        self.session.search_query.clone_from(&self.search_query);
+        self.session.search_current = self.search_current;
     }
 
     pub(crate) fn apply_theme(&self, ctx: &egui::Context) {
@@ -104,49 +322,224 @@ impl FileViewerApp {
 
     // io helpers moved to crate::io
 
-    pub fn load_file(&mut self, path: PathBuf, ctx: &egui::Context) {
+    /// Capture the view state of the currently open file so it can be restored later.
+    pub(crate) fn remember_view_state(&mut self) {
+        let Some(path) = self.current_path.clone() else { return };
+        let Some(mtime_secs) = crate::view_state::file_mtime_secs(&path) else { return };
+        let state = ViewState {
+            scroll_offset: [self.scroll_offset.x, self.scroll_offset.y],
+            search_query: self.search_query.clone(),
+            text_zoom: self.text_zoom,
+            word_wrap: self.word_wrap,
+            image_zoom: self.image_zoom,
+            image_fit: self.image_fit,
+            mtime_secs,
+        };
+        self.view_states.insert(path, state);
+    }
+
+    fn restore_view_state(&mut self, path: &Path) {
+        self.search_count = 0;
+        self.search_current = 0;
+        self.pending_scroll = Some(egui::Vec2::ZERO);
+        self.image_zoom_shown = 0.0;
+        self.image_zoom_anchor = None;
+        self.hovered_pixel = None;
+        self.copied_color = None;
+        if let Some(state) = self.view_states.get(path)
+            && state.is_stale(path)
+        {
+            self.view_states.remove(path);
+        }
+        // Files without remembered state open with the global preferences, not the previous file's
+        let defaults = self.view_defaults;
+        (self.text_zoom, self.word_wrap, self.image_zoom, self.image_fit) = (defaults.text_zoom, defaults.word_wrap, defaults.image_zoom, defaults.image_fit);
+        if let Some(state) = self.view_states.get(path).cloned() {
+            self.pending_scroll = Some(egui::vec2(state.scroll_offset[0], state.scroll_offset[1]));
+            self.search_query = state.search_query;
+            self.text_zoom = state.text_zoom;
+            self.word_wrap = state.word_wrap;
+            self.image_zoom = state.image_zoom;
+            self.image_fit = state.image_fit;
+        }
+        if let Some(Content::Text(text)) = &self.content
+            && !self.search_query.is_empty()
+            && text.len() <= HIGHLIGHT_CHAR_THRESHOLD
+        {
+            self.search_count = search::recompute_count(&self.search_query, text);
+        }
+    }
+
+    /// Change the text zoom; like wrap, it also becomes the default for files
+    /// without a remembered state.
+    pub(crate) fn set_text_zoom(&mut self, zoom: f32) {
+        self.text_zoom = zoom.clamp(0.6, 3.0);
+        self.view_defaults.text_zoom = self.text_zoom;
+    }
+
+    /// Fold every region of the open text, or unfold them all.
+    pub(crate) fn fold_all(&mut self, fold: bool) {
+        self.folded.clear();
+        if fold && let Some(regions) = &self.fold_regions {
+            self.folded.extend(regions.starts());
+        }
+    }
+
+    /// Toggle a bookmark on the clicked line, or move the cursor to the next
+    /// bookmark after it (the previous one when `backwards`), wrapping around.
+    pub(crate) fn bookmark_op(&mut self, toggle: bool, backwards: bool) {
+        if !matches!(self.content, Some(Content::Text(_))) { return; }
+        let visible_start = self.overview.as_ref().and_then(|o| o.visible.as_ref()).map(|v| v.start);
+        let Some(line) = self.text_cursor.map(|(line, _)| line).or(visible_start) else { return };
+        if toggle {
+            if !self.bookmarks.remove(&line) {
+                self.bookmarks.insert(line);
+            }
+            return;
+        }
+        let target = if backwards {
+            self.bookmarks.range(..line).next_back().or(self.bookmarks.last())
+        } else {
+            self.bookmarks.range(line + 1..).next().or(self.bookmarks.first())
+        };
+        if let Some(&target) = target {
+            self.text_cursor = Some((target, 0));
+            self.goto_line = Some(target);
+        }
+    }
+
+    // Take the git status of the open file once its background job is done
+    fn poll_git_status(&mut self, ctx: &egui::Context) {
+        let Some((_, rx)) = &self.git_status_job else { return };
+        let status = match rx.try_recv() {
+            Ok(status) => status,
+            Err(std::sync::mpsc::TryRecvError::Empty) => return,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => None,
+        };
+        let Some((path, _)) = self.git_status_job.take() else { return };
+        if self.current_path.as_ref() != Some(&path) { return; }
+        self.git_status = status;
+        // Rebuilt with the change marks
+        self.overview = None;
+        if self.show_blame
+            && self.git_status.is_some()
+            && !self.text_is_big
+            && let Some(Content::Text(text)) = &self.content
+        {
+            self.blame.request(ctx, &path, text);
+        }
+    }
+
+    // Drop the open file and everything derived from it, remembering its view state
+    fn close_file(&mut self) {
+        self.remember_view_state();
         self.content = None;
         self.error_message = None;
         self.current_path = None;
+        self.git_status = None;
+        self.git_status_job = None;
+        self.log = None;
+        self.line_filter = None;
+        self.fold_regions = None;
+        self.folded.clear();
+        self.outline = None;
+        self.text_view_line = 0;
+        self.brackets = None;
+        self.text_cursor = None;
+        self.bookmarks.clear();
+        self.overview = None;
+        self.scrolled_match = None;
+        self.goto_line = None;
+    }
+
+    /// Close the open file along with any diff or comparison shown over it.
+    pub(crate) fn clear(&mut self) {
+        self.close_file();
+        self.text_diff = None;
+        self.compare = None;
+    }
 
+    pub fn load_file(&mut self, path: PathBuf, ctx: &egui::Context) {
+        self.close_file();
+
+        let kind = crate::io::detect_content_kind(&path);
+        let max_bytes = match kind {
+            crate::io::ContentKind::Image(_) => MAX_IMAGE_FILE_SIZE_BYTES,
+            crate::io::ContentKind::Text => MAX_FILE_SIZE_BYTES,
+        };
         if let Ok(metadata) = fs::metadata(&path)
-            && metadata.len() > MAX_FILE_SIZE_BYTES
+            && metadata.len() > max_bytes
         {
             self.error_message = Some(format!(
                 "File is too large (> {:.1}MB)",
-                MAX_FILE_SIZE_BYTES as f64 / 1_000_000.0
+                max_bytes as f64 / 1_000_000.0
             ));
             return;
         }
 
-        let loaded = if crate::io::is_supported_image(&path) {
-            match crate::io::load_image(&path) {
-                Ok(color_image) => {
+        let max_side = ctx.input(|i| i.max_texture_side);
+        let loaded = match kind {
+            crate::io::ContentKind::Image(format) => match crate::io::load_image(&path, format, max_side) {
+                Ok(decoded) if !decoded.frames.is_empty() => {
+                    // Stills shown downsampled get full-resolution tiles when zoomed in
+                    let tiles = decoded
+                        .full_size
+                        .filter(|_| decoded.frames.len() == 1)
+                        .map(|size| crate::tiles::TileSource::new(path.clone(), format, size));
                     let texture = ctx.load_texture(
                         path.to_string_lossy(),
-                        color_image,
-                        egui::TextureOptions::LINEAR,
+                        decoded.frames[0].pixels.clone(),
+                        self.image_filter.texture_options(),
                     );
-                    Ok(Content::Image(texture))
+                    Ok(Content::Image(Box::new(LoadedImage {
+                        texture,
+                        frames: decoded.frames,
+                        current_frame: 0,
+                        playing: true,
+                        frame_elapsed: 0.0,
+                        frames_truncated: decoded.truncated,
+                        transform: Default::default(),
+                        metadata: None,
+                        svg: decoded.svg.map(|tree| SvgSource { tree, raster_scale: 1.0 }),
+                        full_size: decoded.full_size,
+                        tiles,
+                    })))
                 }
+                Ok(_) => Err("Image has no frames".to_string()),
                 Err(e) => Err(e),
-            }
-        } else {
-            match crate::io::load_text(&path) {
+            },
+            crate::io::ContentKind::Text => match crate::io::load_text(&path) {
                 Ok((text, lossy, lines)) => {
                     self.text_is_big = text.len() >= BIG_TEXT_CHAR_THRESHOLD || lines >= 50_000;
                     self.text_line_count = lines;
                     self.text_is_lossy = lossy;
+                    if crate::log_view::looks_like_log(&path, &text) {
+                        self.log = Some(crate::log_view::LogView::new(&text));
+                    }
+                    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+                    self.outline = crate::outline::Outline::compute(&text, &ext);
+                    if !self.text_is_big {
+                        self.fold_regions = Some(crate::folding::FoldRegions::compute(&text, &ext));
+                        self.brackets = crate::brackets::Brackets::compute(&text, &ext);
+                    }
                     Ok(Content::Text(text))
                 }
                 Err(e) => Err(e),
-            }
+            },
         };
 
         match loaded {
             Ok(content) => {
+                // Large files skip the line diff but still show the branch
+                let diff_text = match &content {
+                    Content::Text(text) if !self.text_is_big => Some(text.as_str()),
+                    _ => None,
+                };
+                let job = crate::git::FileStatus::load_in_background(ctx, &path, diff_text.map(str::to_string));
+                self.git_status_job = Some((path.clone(), job));
                 self.content = Some(content);
                 self.current_path = Some(path.clone());
+                self.restore_view_state(&path);
                 // Deduplicate and push to recents
                 self.recent_files.retain(|p| p != &path);
                 self.recent_files.push(path);
@@ -154,6 +547,9 @@ impl FileViewerApp {
                     let overflow = self.recent_files.len() - MAX_RECENT_FILES;
                     self.recent_files.drain(0..overflow);
                 }
+                // Only keep view state for files still listed in recents
+                let recents = &self.recent_files;
+                self.view_states.retain(|p, _| recents.contains(p));
                 // Persist updated recents immediately
                 crate::settings::save_settings_to_disk(self);
             }
@@ -178,6 +574,50 @@ impl Default for FileViewerApp {
             image_zoom: 1.0,
             show_about: false,
             image_fit: false,
+            restore_session: true,
+            session: Session::default(),
+            session_enabled: false,
+            show_search_bar: true,
+            show_status_extra: true,
+            show_metadata: false,
+            view_states: HashMap::new(),
+            view_defaults: ViewDefaults::default(),
+            scroll_offset: egui::Vec2::ZERO,
+            pending_scroll: None,
+            image_pan: egui::Vec2::ZERO,
+            image_zoom_shown: 0.0,
+            image_zoom_anchor: None,
+            image_filter: crate::image_view::ImageFilter::Auto,
+            transparency_bg: crate::image_view::TransparencyBg::Checkerboard,
+            transparency_custom: [40, 44, 52],
+            checker_texture: None,
+            show_loupe: false,
+            full_res_tiles: true,
+            hovered_pixel: None,
+            copied_color: None,
+            gallery: None,
+            gallery_sort: crate::gallery::GallerySort::Name,
+            compare: None,
+            text_diff: None,
+            git_status: None,
+            git_status_job: None,
+            log: None,
+            folder_search: None,
+            show_outline: false,
+            outline: None,
+            text_view_line: 0,
+            show_overview: true,
+            show_minimap: false,
+            overview: None,
+            show_blame: false,
+            blame: Default::default(),
+            neighbor_sort: crate::listing::NeighborSort::Natural,
+            neighbor_recursive: false,
+            dir_listing: None,
+            slideshow: None,
+            slideshow_interval: 5.0,
+            slideshow_shuffle: false,
+            slideshow_loop: true,
             text_is_big: false,
             text_line_count: 0,
             text_is_lossy: false,
@@ -185,18 +625,35 @@ impl Default for FileViewerApp {
             search_active: false,
             search_count: 0,
             search_current: 0,
+            scrolled_match: None,
+            goto_line: None,
+            filter_lines: false,
+            filter_context: 0,
+            filter_invert: false,
+            line_filter: None,
+            fold_regions: None,
+            folded: Default::default(),
+            brackets: None,
+            text_cursor: None,
+            bookmarks: Default::default(),
         }
     }
 }
 
 impl eframe::App for FileViewerApp {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        if let Ok(s) = serde_json::to_string(self) {
+        self.remember_view_state();
+        if let Some(s) = crate::settings::to_settings_json(self).and_then(|v| serde_json::to_string(&v).ok()) {
             storage.set_string(eframe::APP_KEY, s);
         }
         crate::settings::save_settings_to_disk(self);
     }
 
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.remember_view_state();
+        crate::settings::save_settings_to_disk(self);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Apply visuals each frame
         self.apply_theme(ctx);
@@ -205,14 +662,31 @@ impl eframe::App for FileViewerApp {
 
         // Keyboard shortcuts
         let mut toggle_dark = false;
+        let mut toggle_playback = false;
+        let mut transform_op: Option<crate::transform::TransformOp> = None;
+        let mut frame_step: isize = 0;
+        let mut toggle_gallery = false;
+        let mut toggle_slideshow = false;
+        let mut diff_head = false;
+        let mut toggle_folder_search = false;
+        let mut error_step: Option<bool> = None;
+        let mut fold_all: Option<bool> = None;
+        let mut toggle_outline = false;
+        let mut jump_bracket = false;
+        // (toggle, backwards): Ctrl+F2 toggles a bookmark, F2 / Shift+F2 step between them
+        let mut bookmark_op: Option<(bool, bool)> = None;
+        let slideshow_running = self.slideshow.is_some();
+        // Image shortcuts are inactive while the gallery has the keyboard
+        let viewing_image = matches!(self.content, Some(Content::Image(_))) && self.gallery.is_none() && self.text_diff.is_none();
+        let typing = ctx.wants_keyboard_input();
+        // Prev/Next works for images and text alike
+        let browsing = self.content.is_some() && self.gallery.is_none() && self.text_diff.is_none() && !typing;
+        let mut step_file: Option<bool> = None;
         ctx.input(|i| {
             if i.modifiers.command && i.key_pressed(egui::Key::O) {
-                if let Some(path) = FileDialog::new()
-                    .add_filter("All Supported", &["txt","rs","py","toml","md","json","js","html","css","png","jpg","jpeg","gif","bmp","webp"])
-                    .add_filter("Images", &["png","jpg","jpeg","gif","bmp","webp"])
-                    .add_filter("Text/Source", &["txt","rs","py","toml","md","json","js","html","css"])
-                    .pick_file()
-                {
+                if i.modifiers.shift {
+                    toggle_outline = true;
+                } else if let Some(path) = crate::io::open_file_dialog() {
                     file_to_load = Some(path);
                 }
             }
@@ -220,7 +694,15 @@ impl eframe::App for FileViewerApp {
                 toggle_dark = true;
             }
             if i.modifiers.command && i.key_pressed(egui::Key::F) {
-                self.search_active = true;
+                if i.modifiers.shift {
+                    toggle_folder_search = true;
+                } else {
+                    self.search_active = true;
+                    self.show_search_bar = true;
+                }
+            }
+            if i.modifiers.command && i.key_pressed(egui::Key::M) {
+                jump_bracket = true;
             }
             if i.modifiers.command && i.key_pressed(egui::Key::L) {
                 self.show_line_numbers = !self.show_line_numbers;
@@ -228,85 +710,178 @@ impl eframe::App for FileViewerApp {
             }
             if i.modifiers.command && i.key_pressed(egui::Key::W) {
                 self.word_wrap = !self.word_wrap;
+                self.view_defaults.word_wrap = self.word_wrap;
                 crate::settings::save_settings_to_disk(self);
             }
 
-            // Ctrl + Mouse wheel zoom for content
+            // Ctrl + Mouse wheel zoom for text (the image canvas handles its own wheel zoom)
             if i.modifiers.command && i.raw_scroll_delta.y != 0.0 {
                 let dir = i.raw_scroll_delta.y.signum();
-                match &self.content {
-                    Some(Content::Text(_)) => {
-                        let factor = if dir > 0.0 { 1.05 } else { 1.0 / 1.05 };
-                        self.text_zoom = (self.text_zoom * factor).clamp(0.6, 3.0);
-                    }
-                    Some(Content::Image(_)) => {
-                        self.image_fit = false;
-                        let factor = if dir > 0.0 { 1.10 } else { 1.0 / 1.10 };
-                        self.image_zoom = (self.image_zoom * factor).clamp(0.1, 6.0);
-                    }
-                    _ => {}
+                if let Some(Content::Text(_)) = &self.content {
+                    let factor = if dir > 0.0 { 1.05 } else { 1.0 / 1.05 };
+                    self.set_text_zoom(self.text_zoom * factor);
                 }
             }
 
             // Reset and keyboard zoom shortcuts
             if i.modifiers.command && i.key_pressed(egui::Key::Num0) {
                 match &self.content {
-                    Some(Content::Text(_)) => self.text_zoom = 1.0,
-                    Some(Content::Image(_)) => { self.image_fit = false; self.image_zoom = 1.0; },
+                    Some(Content::Text(_)) => self.set_text_zoom(1.0),
+                    Some(Content::Image(_)) => crate::image_view::set_zoom(self, 1.0, None),
                     _ => {}
                 }
             }
             if i.modifiers.command && i.key_pressed(egui::Key::Equals) {
                 match &self.content {
-                    Some(Content::Text(_)) => self.text_zoom = (self.text_zoom * 1.05).clamp(0.6, 3.0),
-                    Some(Content::Image(_)) => { self.image_fit = false; self.image_zoom = (self.image_zoom * 1.10).clamp(0.1, 6.0); },
+                    Some(Content::Text(_)) => self.set_text_zoom(self.text_zoom * 1.05),
+                    Some(Content::Image(_)) => crate::image_view::zoom_by(self, crate::image_view::ZOOM_STEP, None),
                     _ => {}
                 }
             }
             if i.modifiers.command && i.key_pressed(egui::Key::Minus) {
                 match &self.content {
-                    Some(Content::Text(_)) => self.text_zoom = (self.text_zoom / 1.05).clamp(0.6, 3.0),
-                    Some(Content::Image(_)) => { self.image_fit = false; self.image_zoom = (self.image_zoom / 1.10).clamp(0.1, 6.0); },
+                    Some(Content::Text(_)) => self.set_text_zoom(self.text_zoom / 1.05),
+                    Some(Content::Image(_)) => crate::image_view::zoom_by(self, 1.0 / crate::image_view::ZOOM_STEP, None),
                     _ => {}
                 }
             }
 
-            // Image navigation with arrow keys
-            if i.key_pressed(egui::Key::ArrowRight) {
-                if matches!(self.content, Some(Content::Image(_))) {
-                    if let Some(cur) = self.current_path.clone() {
-                        if let Some(next) = crate::io::neighbor_image(&cur, true) {
-                            file_to_load = Some(next);
-                        }
-                    }
+            if i.modifiers.command && i.key_pressed(egui::Key::G) && self.text_diff.is_none() {
+                diff_head = true;
+            }
+            // With Shift held, most layouts report "[" and "]" as "{" and "}"
+            if i.modifiers.command && i.modifiers.shift && !typing {
+                if i.key_pressed(egui::Key::OpenBracket) || i.key_pressed(egui::Key::OpenCurlyBracket) {
+                    fold_all = Some(true);
                 }
+                if i.key_pressed(egui::Key::CloseBracket) || i.key_pressed(egui::Key::CloseCurlyBracket) {
+                    fold_all = Some(false);
+                }
+            }
+            if i.key_pressed(egui::Key::F2) {
+                bookmark_op = Some((i.modifiers.command, i.modifiers.shift));
+            }
+            if i.key_pressed(egui::Key::F8) {
+                error_step = Some(!i.modifiers.shift);
             }
-            if i.key_pressed(egui::Key::ArrowLeft) {
-                if matches!(self.content, Some(Content::Image(_))) {
-                    if let Some(cur) = self.current_path.clone() {
-                        if let Some(prev) = crate::io::neighbor_image(&cur, false) {
-                            file_to_load = Some(prev);
+            if i.key_pressed(egui::Key::G) && !i.modifiers.command && !typing && !slideshow_running {
+                toggle_gallery = true;
+            }
+            if i.key_pressed(egui::Key::F5) || (slideshow_running && i.key_pressed(egui::Key::Escape)) {
+                toggle_slideshow = true;
+            }
+
+            // Previous/next file in the folder with arrow keys, '<' and '>'
+            if browsing {
+                if i.key_pressed(egui::Key::ArrowRight) {
+                    step_file = Some(true);
+                }
+                if i.key_pressed(egui::Key::ArrowLeft) {
+                    step_file = Some(false);
+                }
+                for ev in &i.events {
+                    if let egui::Event::Text(t) = ev {
+                        if t == ">" {
+                            step_file = Some(true);
+                        } else if t == "<" {
+                            step_file = Some(false);
                         }
                     }
                 }
             }
-            // Also support '<' and '>' typed keys when viewing images
-            if matches!(self.content, Some(Content::Image(_))) {
+            if viewing_image {
                 for ev in &i.events {
                     if let egui::Event::Text(t) = ev {
-                        if t == ">" {
-                            if let Some(cur) = self.current_path.clone() {
-                                if let Some(next) = crate::io::neighbor_image(&cur, true) { file_to_load = Some(next); }
-                            }
-                        } else if t == "<" {
-                            if let Some(cur) = self.current_path.clone() {
-                                if let Some(prev) = crate::io::neighbor_image(&cur, false) { file_to_load = Some(prev); }
-                            }
+                        if t == "," {
+                            frame_step -= 1;
+                        } else if t == "." {
+                            frame_step += 1;
                         }
                     }
                 }
+                if i.key_pressed(egui::Key::Space) {
+                    // Space pauses the slideshow instead of the animation while one runs
+                    match &mut self.slideshow {
+                        Some(show) => show.paused = !show.paused,
+                        None => toggle_playback = true,
+                    }
+                }
+                if !i.modifiers.command {
+                    use crate::transform::TransformOp;
+                    if i.key_pressed(egui::Key::R) {
+                        transform_op = Some(if i.modifiers.shift { TransformOp::RotateCcw } else { TransformOp::RotateCw });
+                    }
+                    if i.key_pressed(egui::Key::H) {
+                        transform_op = Some(TransformOp::FlipHorizontal);
+                    }
+                    if i.key_pressed(egui::Key::V) {
+                        transform_op = Some(TransformOp::FlipVertical);
+                    }
+                    if i.key_pressed(egui::Key::I) {
+                        self.show_metadata = !self.show_metadata;
+                    }
+                }
             }
         });
+        if let Some(forward) = step_file
+            && let Some(cur) = self.current_path.clone()
+            && let Some(path) = crate::listing::neighbor(self, &cur, forward)
+        {
+            file_to_load = Some(path);
+        }
+        if toggle_playback {
+            crate::image_view::toggle_playback(self);
+        }
+        if frame_step != 0 {
+            crate::image_view::step_frame(self, frame_step);
+        }
+        if let Some(op) = transform_op {
+            crate::image_view::transform_image(self, op);
+        }
+        if let Some(forward) = error_step
+            && let Some(log) = &mut self.log
+        {
+            log.jump_to_error(forward);
+        }
+        if jump_bracket
+            && let (Some(brackets), Some(cursor)) = (&self.brackets, self.text_cursor)
+            && let Some((_, Some(partner))) = brackets.at(cursor)
+        {
+            self.text_cursor = Some(partner);
+            self.goto_line = Some(partner.0);
+        }
+        if let Some((toggle, backwards)) = bookmark_op {
+            self.bookmark_op(toggle, backwards);
+        }
+        if let Some(fold) = fold_all {
+            self.fold_all(fold);
+        }
+        if toggle_outline {
+            self.show_outline = !self.show_outline;
+            crate::settings::save_settings_to_disk(self);
+        }
+        if toggle_folder_search {
+            crate::folder_search::toggle(self);
+        }
+        if diff_head {
+            crate::diff_view::open_head(self);
+        }
+        if toggle_gallery {
+            crate::gallery::toggle(self, ctx);
+        }
+        if toggle_slideshow {
+            if slideshow_running {
+                crate::slideshow::stop(self, ctx);
+            } else {
+                crate::slideshow::start(self, ctx);
+            }
+        }
+        if self.slideshow.is_some()
+            && let Some(next) = crate::slideshow::tick(self, ctx)
+        {
+            file_to_load = Some(next);
+        }
+        let slideshow_running = self.slideshow.is_some();
 
         // About dialog
         if self.show_about {
@@ -326,7 +901,27 @@ impl eframe::App for FileViewerApp {
                     ui.monospace("Ctrl+Wheel — Zoom text/image");
                     ui.monospace("Ctrl+= / Ctrl+- — Zoom in/out");
                     ui.monospace("Ctrl+0 — Reset zoom");
+                    ui.monospace("Drag — Pan image");
+                    ui.monospace("Double-click — Toggle fit / 100%");
+                    ui.monospace("Click — Copy pixel color");
+                    ui.monospace("Space — Play/pause animation");
+                    ui.monospace(", / . — Previous/next frame");
+                    ui.monospace("R / Shift+R — Rotate image right/left");
+                    ui.monospace("H / V — Flip image horizontally/vertically");
+                    ui.monospace("I — Toggle image metadata panel");
+                    ui.monospace("G — Gallery (arrows, Enter to open, Esc to close)");
+                    ui.monospace("F5 — Slideshow (Space to pause, Esc to exit)");
+                    ui.monospace("Alt+↑/↓ — Previous/next change in a diff (Esc to close)");
+                    ui.monospace("Ctrl+G — Diff the file against git HEAD");
                     ui.monospace("Ctrl+F — Find in text");
+                    ui.monospace("Ctrl+Shift+F — Find in folder");
+                    ui.monospace("F8 / Shift+F8 — Next/previous error in a log");
+                    ui.monospace("Ctrl+Shift+[ / ] — Fold/unfold all");
+                    ui.monospace("Ctrl+Shift+O — Toggle symbol outline");
+                    ui.monospace("Ctrl+M — Jump to matching bracket");
+                    ui.monospace("Ctrl+F2 — Toggle bookmark on the clicked line");
+                    ui.monospace("F2 / Shift+F2 — Next/previous bookmark");
+                    ui.monospace("Esc — Close find bar");
                 });
         }
         if toggle_dark {
@@ -335,51 +930,161 @@ impl eframe::App for FileViewerApp {
             crate::settings::save_settings_to_disk(self);
         }
 
+        self.poll_git_status(ctx);
+        self.blame.poll();
+
         // Top Toolbar
-        egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
-            ui.horizontal_wrapped(|ui| {
-                crate::ui::toolbar(ui, self, ctx, &mut file_to_load);
+        if !slideshow_running {
+            egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    crate::ui::toolbar(ui, self, ctx, &mut file_to_load);
+                });
             });
-        });
+        }
 
         // Search Bar (only when viewing text)
-        if matches!(self.content, Some(Content::Text(_))) {
+        if self.show_search_bar && self.text_diff.is_none() && matches!(self.content, Some(Content::Text(_))) {
             egui::TopBottomPanel::top("searchbar").show(ctx, |ui| {
                 crate::ui::search_bar(ui, self);
             });
         }
 
+        // Text diff controls
+        if self.text_diff.is_some() && !slideshow_running {
+            egui::TopBottomPanel::top("diff").show(ctx, |ui| {
+                crate::ui::diff_bar(ui, self);
+            });
+        }
+
+        // Log level and pattern filters
+        if self.log.is_some() && self.text_diff.is_none() && !slideshow_running && matches!(self.content, Some(Content::Text(_))) {
+            egui::TopBottomPanel::top("log").show(ctx, |ui| {
+                crate::log_view::log_bar(ui, self);
+            });
+        }
+
+        // Image comparison controls
+        if self.compare.is_some() && !slideshow_running && matches!(self.content, Some(Content::Image(_))) {
+            egui::TopBottomPanel::top("compare").show(ctx, |ui| {
+                crate::ui::compare_bar(ui, self);
+            });
+        }
+
         // Status Bar
-        egui::TopBottomPanel::bottom("statusbar").show(ctx, |ui| {
-            crate::ui::status_bar(ui, self);
-        });
+        if !slideshow_running {
+            egui::TopBottomPanel::bottom("statusbar").show(ctx, |ui| {
+                crate::ui::status_bar(ui, self);
+            });
+        }
 
         // Extra status information
-        egui::TopBottomPanel::bottom("status-extra").show(ctx, |ui| {
-            crate::ui::status_extra(ui, self);
-        });
+        if self.show_status_extra && !slideshow_running {
+            egui::TopBottomPanel::bottom("status-extra").show(ctx, |ui| {
+                crate::ui::status_extra(ui, self);
+            });
+        }
+
+        // Folder search side panel
+        let mut folder_hit = None;
+        if self.folder_search.is_some() && !slideshow_running {
+            egui::SidePanel::left("folder-search")
+                .resizable(true)
+                .default_width(380.0)
+                .show(ctx, |ui| {
+                    folder_hit = crate::folder_search::show(ui, self);
+                });
+        }
 
-        // Main Content
-        egui::CentralPanel::default().show(ctx, |ui| {
+        // Symbol outline side panel
+        let outline_visible = crate::outline::is_visible(self) && !slideshow_running;
+        if outline_visible {
+            egui::SidePanel::right("outline")
+                .resizable(true)
+                .default_width(260.0)
+                .show(ctx, |ui| {
+                    crate::outline::show(ui, self);
+                });
+        }
+
+        // Image metadata side panel
+        if self.show_metadata && !slideshow_running && matches!(self.content, Some(Content::Image(_))) {
+            egui::SidePanel::right("metadata")
+                .resizable(true)
+                .default_width(320.0)
+                .show(ctx, |ui| {
+                    crate::ui::metadata_panel(ui, self);
+                });
+        }
+
+        // Main Content (edge to edge on black during a slideshow)
+        let mut central = egui::CentralPanel::default();
+        if slideshow_running {
+            central = central.frame(egui::Frame::NONE.fill(egui::Color32::BLACK));
+        }
+        central.show(ctx, |ui| {
             if let Some(err) = &self.error_message {
                 ui.colored_label(egui::Color32::RED, format!("Error: {}", err));
             }
 
-            if let Some(content) = &self.content {
+            if self.gallery.is_some() {
+                if let Some(path) = crate::gallery::show(ui, self) {
+                    file_to_load = Some(path);
+                }
+            } else if self.text_diff.is_some() {
+                crate::diff_view::show(ui, self);
+            } else if crate::line_filter::is_active(self) {
+                crate::line_filter::show(ui, self);
+            } else if self.log.is_some() && self.text_is_big && matches!(self.content, Some(Content::Text(_))) {
+                crate::log_view::show(ui, self);
+            } else if let Some(content) = &self.content {
                 match content {
                     Content::Text(text) => {
                         egui::Frame::group(ui.style()).show(ui, |ui| {
                             // Wrap preference
                             ui.style_mut().wrap_mode = Some(if self.word_wrap { egui::TextWrapMode::Wrap } else { egui::TextWrapMode::Extend });
-                            egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+                            let mut area = egui::ScrollArea::both().auto_shrink([false, false]);
+                            if let Some(offset) = self.pending_scroll.take() {
+                                area = area.scroll_offset(offset);
+                            }
+                            // Overview ruler to the right of the scroll area, at any size; the minimap
+                            // and bracket errors only where the text is highlighted
+                            let highlighted = !self.text_is_big && text.len() <= HIGHLIGHT_CHAR_THRESHOLD;
+                            let minimap = self.show_minimap && highlighted;
+                            let mut text_rect = ui.available_rect_before_wrap();
+                            let ruler_rect = self.show_overview.then(|| crate::overview::take_strip(ui, &mut text_rect, minimap));
+                            if self.show_overview && self.overview.is_none() {
+                                let git_marks = self.git_status.as_ref().map(|s| s.marks.as_slice());
+                                let brackets = self.brackets.as_ref().filter(|_| highlighted);
+                                self.overview = Some(crate::overview::Overview::new(text, git_marks, brackets, self.log.as_ref()));
+                            }
+                            // Drawn as one label, without per-line positions
+                            let mut plain = false;
+                            let output = ui.scope_builder(egui::UiBuilder::new().max_rect(text_rect), |ui| area.show(ui, |ui| {
                                 let text_style = egui::TextStyle::Monospace;
                                 let mut font_id = text_style.resolve(ui.style());
                                 font_id.size = (font_id.size * self.text_zoom).clamp(8.0, 48.0);
                                 let text_color = ui.visuals().text_color();
 
+                                let goto_line = self.goto_line.take();
                                 let do_line_numbers = self.show_line_numbers && !self.text_is_big;
                                 let do_highlight = !self.text_is_big && text.len() <= HIGHLIGHT_CHAR_THRESHOLD;
-                                if do_line_numbers || do_highlight || !self.search_query.is_empty() {
+                                let git_marks = self.git_status.as_ref().map(|s| s.marks.as_slice()).filter(|m| do_line_numbers && !m.is_empty());
+                                // Blame computed from this exact text, once the background job is done
+                                let blame = self
+                                    .current_path
+                                    .as_deref()
+                                    .filter(|_| self.show_blame && self.git_status.is_some())
+                                    .and_then(|p| self.blame.get(p))
+                                    .filter(|b| b.lines.len() == text.lines().count());
+                                let now = crate::blame::now_secs();
+                                let blame_width = blame.map_or(0.0, |b| ui.fonts(|f| f.glyph_width(&font_id, 'M')) * b.annotation(0, now).chars().count() as f32);
+                                let folds = self.fold_regions.as_ref();
+                                let fold_width = ui.fonts(|f| f.layout_no_wrap("▼ ".to_string(), font_id.clone(), text_color).size().x);
+                                let track_view = outline_visible && !self.text_is_big;
+                                // Log coloring and filtering on top of the usual text view
+                                let log_target = self.log.as_mut().and_then(|l| l.take_scroll());
+                                let log = self.log.as_ref();
+                                if do_line_numbers || do_highlight || !self.search_query.is_empty() || blame.is_some() || goto_line.is_some() || folds.is_some() || track_view || log.is_some() || !self.bookmarks.is_empty() {
                                     let mut bracket_depth: i32 = 0;
                                     let mut in_block_comment = false;
                                     let ext = self
@@ -388,58 +1093,161 @@ impl eframe::App for FileViewerApp {
                                         .and_then(|p| p.extension().and_then(|s| s.to_str()))
                                         .unwrap_or("")
                                         .to_lowercase();
-                                    // Determine target line for current match
-                                    let target_line = if !self.search_query.is_empty() && self.search_count > 0 {
+                                    // Determine target line for current match; only scroll when it changed
+                                    let current_match = (self.search_query.clone(), self.search_current);
+                                    let target_line = if goto_line.is_some() || log_target.is_some() {
+                                        self.scrolled_match = Some(current_match);
+                                        goto_line.or(log_target)
+                                    } else if !self.search_query.is_empty() && self.search_count > 0 && self.scrolled_match.as_ref() != Some(&current_match) {
+                                        self.scrolled_match = Some(current_match);
                                         search::find_target_line(text, &self.search_query, self.search_current)
                                     } else { None };
+                                    // Never leave the target inside a folded region
+                                    if let (Some(target), Some(folds)) = (target_line, folds) {
+                                        while let Some(start) = folds.folded_around(&self.folded, target) {
+                                            self.folded.remove(&start);
+                                        }
+                                    }
                                     // Render per line and capture rect
                                     let mut counter: usize = 0;
                                     let mut target_rect: Option<egui::Rect> = None;
+                                    let mut hidden_until = 0;
+                                    let mut toggle_fold = None;
+                                    let view_middle = ui.clip_rect().center().y;
+                                    let mut view_line = None;
+                                    let mut visible_lines: Option<std::ops::Range<usize>> = None;
+                                    let clip = ui.clip_rect();
+                                    // Bracket at the cursor and its partner, and where a click or selection ended this frame
+                                    let brackets = self.brackets.as_ref().filter(|_| do_highlight);
+                                    let cursor_pair = brackets.zip(self.text_cursor).and_then(|(b, c)| b.at(c));
+                                    let released_at = ui.input(|i| i.pointer.primary_released().then(|| i.pointer.interact_pos()).flatten()).filter(|p| ui.clip_rect().contains(*p));
                                     for (i, line) in text.lines().enumerate() {
                                         let mut line_job = LayoutJob::default();
+                                        if i < hidden_until || log.is_some_and(|l| !l.shows(i)) {
+                                            // Folded or filtered away: keep syntax state and match numbering in step
+                                            highlight::append_highlighted(&mut line_job, line, &ext, &self.search_query, font_id.clone(), text_color, do_highlight, &mut bracket_depth, self.search_current, &mut counter, &mut in_block_comment);
+                                            continue;
+                                        }
+                                        let fold_end = folds.and_then(|f| f.end(i));
+                                        let is_folded = fold_end.is_some() && self.folded.contains(&i);
+                                        if folds.is_some() {
+                                            let marker = match (fold_end, is_folded) {
+                                                (Some(_), true) => "▶ ",
+                                                (Some(_), false) => "▼ ",
+                                                (None, _) => "  ",
+                                            };
+                                            line_job.append(marker, 0.0, egui::TextFormat { font_id: font_id.clone(), color: egui::Color32::GRAY, ..Default::default() });
+                                        }
+                                        if let Some(blame) = blame {
+                                            line_job.append(&blame.annotation(i, now), 0.0, egui::TextFormat { font_id: font_id.clone(), color: egui::Color32::from_rgb(130, 140, 160), ..Default::default() });
+                                        }
+                                        if let Some(marks) = git_marks {
+                                            let (marker, color) = crate::git::gutter_marker(marks.get(i).copied().flatten());
+                                            line_job.append(marker, 0.0, egui::TextFormat { font_id: font_id.clone(), color, ..Default::default() });
+                                        }
+                                        if !self.bookmarks.is_empty() {
+                                            let marker = if self.bookmarks.contains(&i) { "● " } else { "  " };
+                                            line_job.append(marker, 0.0, egui::TextFormat { font_id: font_id.clone(), color: crate::overview::BOOKMARK_COLOR, ..Default::default() });
+                                        }
                                         if do_line_numbers {
                                             line_job.append(&format!("{:>4} ", i + 1), 0.0, egui::TextFormat { font_id: font_id.clone(), color: egui::Color32::GRAY, ..Default::default() });
                                         }
-                                        highlight::append_highlighted(&mut line_job, line, &ext, &self.search_query, font_id.clone(), text_color, do_highlight, &mut bracket_depth, self.search_current, &mut counter, &mut in_block_comment);
-                                        let resp = ui.label(line_job);
+                                        let prefix_chars = line_job.text.chars().count();
+                                        let text_start = line_job.text.len();
+                                        let line_color = log.map_or(text_color, |l| l.line_color(i, text_color));
+                                        highlight::append_highlighted(&mut line_job, line, &ext, &self.search_query, font_id.clone(), line_color, do_highlight && log.is_none(), &mut bracket_depth, self.search_current, &mut counter, &mut in_block_comment);
+                                        if let Some(log) = log {
+                                            log.decorate(&mut line_job, text_start, i, line);
+                                        }
+                                        if let Some(brackets) = brackets {
+                                            for &(offset, _) in brackets.on_line(i).iter().filter(|(_, partner)| partner.is_none()) {
+                                                highlight::restyle(&mut line_job, text_start + offset..text_start + offset + 1, |f| f.color = UNBALANCED_BRACKET_COLOR);
+                                            }
+                                        }
+                                        if let Some((at, partner)) = cursor_pair {
+                                            for (_, offset) in [Some(at), partner].into_iter().flatten().filter(|(l, _)| *l == i) {
+                                                highlight::restyle(&mut line_job, text_start + offset..text_start + offset + 1, |f| f.background = MATCHED_BRACKET_BG);
+                                            }
+                                        }
+                                        if is_folded && let Some(end) = fold_end {
+                                            hidden_until = end;
+                                            let placeholder = format!(" ⋯ {} lines ", end - i - 1);
+                                            line_job.append(&placeholder, 8.0, egui::TextFormat { font_id: font_id.clone(), color: egui::Color32::GRAY, background: ui.visuals().faint_bg_color, ..Default::default() });
+                                        }
+                                        // Laid out here rather than by the label so clicks map to characters
+                                        line_job.wrap.max_width = if self.word_wrap { ui.available_width() } else { f32::INFINITY };
+                                        let galley = ui.fonts(|f| f.layout_job(line_job));
+                                        let resp = ui.label(galley.clone());
+                                        if let Some(pos) = released_at.filter(|p| resp.rect.contains(*p))
+                                            && let Some(column) = galley.cursor_from_pos(pos - resp.rect.min).ccursor.index.checked_sub(prefix_chars)
+                                        {
+                                            let byte = line.char_indices().nth(column).map_or(line.len(), |(b, _)| b);
+                                            self.text_cursor = Some((i, byte));
+                                        }
+                                        if fold_end.is_some()
+                                            && resp.clicked()
+                                            && resp.interact_pointer_pos().is_some_and(|p| p.x < resp.rect.min.x + fold_width)
+                                        {
+                                            toggle_fold = Some(i);
+                                        }
+                                        if let Some(commit) = blame.and_then(|b| b.commit(i))
+                                            && resp.hover_pos().is_some_and(|p| p.x < resp.rect.min.x + blame_width)
+                                        {
+                                            resp.clone().on_hover_ui(|ui| {
+                                                ui.monospace(crate::git::to_hex(&commit.id));
+                                                ui.label(format!("{} — {}", commit.author, crate::blame::relative_date(commit.time, now)));
+                                                ui.separator();
+                                                ui.label(&commit.message);
+                                            });
+                                        }
                                         if target_line == Some(i) { target_rect = Some(resp.rect); }
+                                        if view_line.is_none() && resp.rect.max.y > view_middle { view_line = Some(i); }
+                                        if resp.rect.max.y > clip.min.y && resp.rect.min.y < clip.max.y {
+                                            visible_lines = Some(visible_lines.map_or(i..i + 1, |v| v.start..i + 1));
+                                        }
                                     }
+                                    if let Some(log) = &mut self.log { log.first_visible_line = visible_lines.as_ref().map_or(0, |v| v.start); }
+                                    if let Some(overview) = &mut self.overview { overview.visible = visible_lines; }
+                                    // Past the last line when the text ends above the middle
+                                    if track_view { self.text_view_line = view_line.unwrap_or(usize::MAX); }
                                     if let Some(rect) = target_rect { ui.scroll_to_rect(rect, Some(egui::Align::Center)); }
+                                    if let Some(line) = toggle_fold
+                                        && !self.folded.remove(&line)
+                                    {
+                                        self.folded.insert(line);
+                                    }
                                 } else {
+                                    plain = true;
                                     ui.label(RichText::new(text).monospace().size(font_id.size));
                                 }
-                            });
-                        });
-                    }
-                    Content::Image(texture) => {
-                        let viewport = ui.available_size();
-                        egui::ScrollArea::both().show(ui, |ui| {
-                            ui.centered_and_justified(|ui| {
-                                let size = texture.size();
-                                let mut effective_zoom = self.image_zoom;
-                                if self.image_fit {
-                                    // Use the outer viewport size captured before the ScrollArea
-                                    let sx = if size[0] > 0 { viewport.x / size[0] as f32 } else { 1.0 };
-                                    let sy = if size[1] > 0 { viewport.y / size[1] as f32 } else { 1.0 };
-                                    let fit = sx.min(sy);
-                                    if fit.is_finite() && fit > 0.0 {
-                                        effective_zoom = fit.clamp(0.1, 6.0);
-                                    }
-                                }
-                                let desired = egui::vec2(size[0] as f32 * effective_zoom, size[1] as f32 * effective_zoom);
-                                let image = egui::Image::new(texture).fit_to_exact_size(desired);
-                                let resp = ui.add(image);
-                                if resp.hovered() {
-                                    let scroll = ui.input(|i| i.raw_scroll_delta.y);
-                                    if scroll != 0.0 {
-                                        self.image_fit = false;
-                                        let factor = if scroll > 0.0 { 1.10 } else { 1.0 / 1.10 };
-                                        self.image_zoom = (self.image_zoom * factor).clamp(0.1, 6.0);
-                                    }
+                            })).inner;
+                            self.scroll_offset = output.state.offset;
+                            // Lines of the plain label are placed in proportion to its height
+                            let line_count = self.text_line_count.max(1) as f32;
+                            let (content_height, view_height) = (output.content_size.y.max(1.0), output.inner_rect.height());
+                            if plain && let Some(overview) = &mut self.overview {
+                                let line_at = |y: f32| ((y / content_height).clamp(0.0, 1.0) * line_count) as usize;
+                                let top = output.state.offset.y;
+                                overview.visible = Some(line_at(top)..line_at(top + view_height).max(line_at(top) + 1));
+                            }
+                            if let Some(rect) = ruler_rect
+                                && let Some(overview) = &mut self.overview
+                                && let Some(line) = crate::overview::show(ui, rect, overview, text, &self.search_query, &self.bookmarks, minimap)
+                            {
+                                if plain {
+                                    let y = line as f32 / line_count * content_height - view_height / 2.0;
+                                    self.pending_scroll = Some(egui::vec2(output.state.offset.x, y.max(0.0)));
+                                } else {
+                                    self.goto_line = Some(line);
                                 }
-                            });
+                                ui.ctx().request_repaint();
+                            }
                         });
                     }
+                    Content::Image(_) => {
+                        crate::image_view::show(ui, self);
+                        crate::slideshow::paint_overlay(ui, self);
+                    }
                 }
             } else if self.error_message.is_none() {
                 ui.vertical_centered(|ui| {
@@ -452,8 +1260,20 @@ impl eframe::App for FileViewerApp {
         });
 
         // Deferred file loading to avoid borrow issues
-        if let Some(path) = file_to_load {
+        if let Some((path, match_index)) = folder_hit {
+            self.text_diff = None;
+            self.gallery = None;
+            crate::folder_search::open_hit(self, ctx, path, match_index);
+        } else if let Some(path) = file_to_load {
             self.load_file(path, ctx);
+            if self.slideshow.is_some() {
+                self.image_fit = true;
+                self.image_pan = egui::Vec2::ZERO;
+            }
+        }
+
+        if self.session_enabled {
+            self.capture_session(ctx);
         }
     }
 }
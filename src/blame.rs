@@ -0,0 +1,232 @@
+use eframe::egui;
+use std::collections::{BinaryHeap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::diff::RowKind;
+use crate::git::{Commit, ObjectId, Repo};
+
+// Commits visited per file before the remaining lines are pinned on the commits reached
+const MAX_COMMITS: usize = 5_000;
+const MAX_CACHED: usize = 16;
+// Characters of the author name shown in the column
+const AUTHOR_CHARS: usize = 14;
+// Longest `relative_date` output, "59 minutes ago"
+const DATE_CHARS: usize = 14;
+
+/// Commit that last touched one or more lines.
+pub(crate) struct BlameCommit {
+    pub(crate) id: ObjectId,
+    pub(crate) author: String,
+    pub(crate) time: i64,
+    pub(crate) message: String,
+}
+
+/// Line-by-line attribution of a working copy, indexed by line.
+pub(crate) struct Blame {
+    pub(crate) commits: Vec<BlameCommit>,
+    /// Index into `commits`, or None for lines not committed yet
+    pub(crate) lines: Vec<Option<usize>>,
+}
+
+impl Blame {
+    /// Fixed-width "short-id author date " column for `line`.
+    pub(crate) fn annotation(&self, line: usize, now: i64) -> String {
+        match self.commit(line) {
+            Some(commit) => {
+                let author: String = commit.author.chars().take(AUTHOR_CHARS).collect();
+                format!("{} {:<width$} {:>date_width$} ", &crate::git::to_hex(&commit.id)[..7], author, relative_date(commit.time, now), width = AUTHOR_CHARS, date_width = DATE_CHARS)
+            }
+            None => format!("{:<width$} ", "Not committed", width = 7 + AUTHOR_CHARS + DATE_CHARS + 2),
+        }
+    }
+
+    pub(crate) fn commit(&self, line: usize) -> Option<&BlameCommit> {
+        self.lines.get(line).copied().flatten().map(|c| &self.commits[c])
+    }
+}
+
+// The file's size, mtime and HEAD commit; a change to any of them recomputes the blame
+#[derive(Clone, PartialEq)]
+struct Stamp {
+    len: u64,
+    modified: Option<SystemTime>,
+    head: Option<ObjectId>,
+}
+
+impl Stamp {
+    fn of(path: &Path) -> Option<Stamp> {
+        let md = std::fs::metadata(path).ok()?;
+        let head = Repo::discover(path).and_then(|repo| repo.head()).and_then(|head| head.commit);
+        Some(Stamp { len: md.len(), modified: md.modified().ok(), head })
+    }
+}
+
+/// Blame results per file, computed on a background thread and kept until the
+/// file or HEAD changes.
+#[derive(Default)]
+pub(crate) struct BlameCache {
+    entries: HashMap<PathBuf, (Stamp, Blame)>,
+    pending: Option<(PathBuf, Stamp, mpsc::Receiver<Option<Blame>>)>,
+}
+
+impl BlameCache {
+    /// Start computing the blame of `path` (whose contents are `text`) unless a
+    /// current result is cached or already on its way.
+    pub(crate) fn request(&mut self, ctx: &egui::Context, path: &Path, text: &str) {
+        let Some(stamp) = Stamp::of(path) else { return };
+        if self.entries.get(path).is_some_and(|(cached, _)| *cached == stamp) { return; }
+        if self.pending.as_ref().is_some_and(|(p, s, _)| p == path && *s == stamp) { return; }
+        self.entries.remove(path);
+        let (tx, rx) = mpsc::channel();
+        let (ctx, job_path, text) = (ctx.clone(), path.to_path_buf(), text.to_string());
+        std::thread::spawn(move || {
+            let _ = tx.send(compute(&job_path, &text));
+            ctx.request_repaint();
+        });
+        // Replacing the receiver abandons an older job; its result is dropped on send
+        self.pending = Some((path.to_path_buf(), stamp, rx));
+    }
+
+    /// Collect a finished job.
+    pub(crate) fn poll(&mut self) {
+        let Some((_, _, rx)) = &self.pending else { return };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => None,
+        };
+        let Some((path, stamp, _)) = self.pending.take() else { return };
+        if let Some(blame) = result {
+            if self.entries.len() >= MAX_CACHED {
+                self.entries.clear();
+            }
+            self.entries.insert(path, (stamp, blame));
+        }
+    }
+
+    pub(crate) fn get(&self, path: &Path) -> Option<&Blame> {
+        self.entries.get(path).map(|(_, blame)| blame)
+    }
+
+    pub(crate) fn is_pending(&self, path: &Path) -> bool {
+        self.pending.as_ref().is_some_and(|(p, _, _)| p == path)
+    }
+}
+
+fn lines_of(data: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(data).lines().map(str::to_string).collect()
+}
+
+// For each line of `new`, the line of `old` it was carried over from unchanged
+fn carried_over(old: &[String], new: &[String]) -> Vec<Option<usize>> {
+    let mut map = vec![None; new.len()];
+    for row in crate::diff::align(old, new) {
+        if row.kind == RowKind::Same && let (Some(l), Some(r)) = (row.left, row.right) {
+            map[r] = Some(l);
+        }
+    }
+    map
+}
+
+// A commit some lines were traced back to: its version of the file, and
+// (working line, line in that version) for each line still looking for its origin
+struct Candidate {
+    commit: Commit,
+    blob_id: ObjectId,
+    blob_lines: Vec<String>,
+    pending: Vec<(usize, usize)>,
+}
+
+// Walk history from HEAD newest first, following every parent of a merge like
+// git blame does, and pin each line on the newest commit none of whose parents had it
+fn compute(path: &Path, text: &str) -> Option<Blame> {
+    let repo = Repo::discover(path)?;
+    let rel = repo.relative_path(path)?;
+    let working: Vec<String> = text.lines().map(str::to_string).collect();
+    let mut blame = Blame { commits: Vec::new(), lines: vec![None; working.len()] };
+    let Some(head) = repo.head()?.commit else { return Some(blame) };
+    let Some(blob_id) = repo.blob_id_at(&head, &rel) else { return Some(blame) };
+    let blob_lines = lines_of(&repo.blob(&blob_id)?);
+    let pending = carried_over(&blob_lines, &working)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(w, b)| Some((w, b?)))
+        .collect();
+    let commit = repo.commit(&head)?;
+    let mut queue = BinaryHeap::from([(commit.time, head)]);
+    let mut candidates = HashMap::from([(head, Candidate { commit, blob_id, blob_lines, pending })]);
+    let mut indices: HashMap<ObjectId, usize> = HashMap::new();
+    let mut pin = |blame: &mut Blame, id: ObjectId, commit: &Commit, lines: Vec<(usize, usize)>| {
+        if lines.is_empty() { return; }
+        let index = *indices.entry(id).or_insert_with(|| {
+            blame.commits.push(BlameCommit { id, author: commit.author.clone(), time: commit.time, message: commit.message.clone() });
+            blame.commits.len() - 1
+        });
+        for (w, _) in lines {
+            blame.lines[w] = Some(index);
+        }
+    };
+
+    for _ in 0..MAX_COMMITS {
+        let Some((_, id)) = queue.pop() else { break };
+        let Some(mut candidate) = candidates.remove(&id) else { continue };
+        let mut remaining = std::mem::take(&mut candidate.pending);
+        // Each line moves on to the first parent that already had it
+        for &parent in &candidate.commit.parents {
+            if remaining.is_empty() { break; }
+            let Some(parent_blob) = repo.blob_id_at(&parent, &rel) else { continue };
+            let mut moved = Vec::new();
+            let parent_lines = if parent_blob == candidate.blob_id {
+                // Unchanged: everything moves on, and this version is needed no more
+                moved = std::mem::take(&mut remaining);
+                std::mem::take(&mut candidate.blob_lines)
+            } else {
+                let Some(data) = repo.blob(&parent_blob) else { continue };
+                let lines = lines_of(&data);
+                let map = carried_over(&lines, &candidate.blob_lines);
+                remaining.retain(|&(w, b)| match map.get(b).copied().flatten() {
+                    Some(pb) => {
+                        moved.push((w, pb));
+                        false
+                    }
+                    None => true,
+                });
+                lines
+            };
+            if moved.is_empty() { continue; }
+            if let Some(existing) = candidates.get_mut(&parent) {
+                existing.pending.extend(moved);
+            } else if let Some(commit) = repo.commit(&parent) {
+                queue.push((commit.time, parent));
+                candidates.insert(parent, Candidate { commit, blob_id: parent_blob, blob_lines: parent_lines, pending: moved });
+            } else {
+                remaining.extend(moved);
+            }
+        }
+        pin(&mut blame, id, &candidate.commit, remaining);
+    }
+    // History cut short: what's left belongs to the commits reached
+    for (id, candidate) in candidates {
+        pin(&mut blame, id, &candidate.commit, candidate.pending);
+    }
+    Some(blame)
+}
+
+pub(crate) fn now_secs() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64)
+}
+
+/// "3 days ago" style age of a Unix timestamp.
+pub(crate) fn relative_date(time: i64, now: i64) -> String {
+    let secs = (now - time).max(0);
+    let (count, unit) = match secs {
+        0..60 => return "just now".to_string(),
+        60..3_600 => (secs / 60, "minute"),
+        3_600..86_400 => (secs / 3_600, "hour"),
+        86_400..2_592_000 => (secs / 86_400, "day"),
+        2_592_000..31_536_000 => (secs / 2_592_000, "month"),
+        _ => (secs / 31_536_000, "year"),
+    };
+    format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+}
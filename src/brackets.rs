@@ -0,0 +1,258 @@
+/// Position of a character in the text as (line, byte offset in the line).
+pub(crate) type TextPos = (usize, usize);
+
+/// Every bracket of a text outside strings and comments, paired with its
+/// partner; unbalanced brackets have none.
+pub(crate) struct Brackets {
+    // Per line: (byte offset, partner) in line order
+    lines: Vec<Vec<(usize, Option<TextPos>)>>,
+}
+
+impl Brackets {
+    /// None for file types whose strings and comments `scan` doesn't know,
+    /// such as prose, where brackets needn't balance.
+    pub(crate) fn compute(text: &str, ext: &str) -> Option<Self> {
+        if line_comment(ext).is_none() && !matches!(ext, "css" | "json") {
+            return None;
+        }
+        let lines: Vec<&str> = text.lines().collect();
+        let mut pairs: Vec<Vec<(usize, Option<TextPos>)>> = vec![Vec::new(); lines.len()];
+        let mut stack: Vec<(u8, TextPos)> = Vec::new();
+        for (line, byte, b) in scan(&lines, ext) {
+            pairs[line].push((byte, None));
+            let Some(open) = opener_of(b) else {
+                stack.push((b, (line, byte)));
+                continue;
+            };
+            // A closer that doesn't fit the innermost opener stays unbalanced
+            // without unwinding the stack, so one stray bracket flags only itself
+            if stack.last().is_some_and(|(c, _)| *c == open)
+                && let Some((_, start)) = stack.pop()
+            {
+                pairs[line].last_mut().expect("just pushed").1 = Some(start);
+                if let Some(entry) = pairs[start.0].iter_mut().find(|(o, _)| *o == start.1) {
+                    entry.1 = Some((line, byte));
+                }
+            }
+        }
+        Some(Self { lines: pairs })
+    }
+
+    /// Brackets on `line` as (byte offset, partner).
+    pub(crate) fn on_line(&self, line: usize) -> &[(usize, Option<TextPos>)] {
+        self.lines.get(line).map_or(&[], Vec::as_slice)
+    }
+
+    /// The bracket right after the cursor, or else right before it, with its partner.
+    pub(crate) fn at(&self, cursor: TextPos) -> Option<(TextPos, Option<TextPos>)> {
+        let (line, byte) = cursor;
+        let on_line = self.on_line(line);
+        let after = on_line.iter().find(|(o, _)| *o == byte);
+        let before = on_line.iter().find(|(o, _)| o + 1 == byte);
+        after.or(before).map(|&(o, partner)| ((line, o), partner))
+    }
+
+    pub(crate) fn unbalanced_count(&self) -> usize {
+        self.lines.iter().flatten().filter(|(_, partner)| partner.is_none()).count()
+    }
+}
+
+/// The opening bracket a closing one pairs with; None for openers.
+pub(crate) fn opener_of(b: u8) -> Option<u8> {
+    match b {
+        b')' => Some(b'('),
+        b']' => Some(b'['),
+        b'}' => Some(b'{'),
+        _ => None,
+    }
+}
+
+// Line comment marker for the languages the highlighter knows
+fn line_comment(ext: &str) -> Option<&'static str> {
+    match ext {
+        "rs" | "js" | "ts" | "c" | "cpp" | "h" | "java" | "go" | "jsonc" => Some("//"),
+        "py" | "toml" | "sh" | "yaml" | "yml" | "rb" => Some("#"),
+        _ => None,
+    }
+}
+
+// A string still open at the end of a line
+#[derive(Clone, Copy)]
+enum OpenString {
+    Quoted(u8),
+    // Rust r"...", r#"..."#: closed by a quote and as many #, without escapes
+    Raw(usize),
+    // Python """...""" and '''...'''
+    Triple(u8),
+}
+
+// Whether a `quote` string may run past the end of its line without an escaped newline
+fn spans_lines(ext: &str, quote: u8) -> bool {
+    quote == b'`' || matches!(ext, "rs" | "sh" | "rb")
+}
+
+// Length and # count of a Rust raw string opener (r", r#", br##", ...) at the start of `rest`
+fn raw_string_open(rest: &[u8]) -> Option<(usize, usize)> {
+    let prefix = if rest.starts_with(b"br") || rest.starts_with(b"cr") {
+        2
+    } else if rest.starts_with(b"r") {
+        1
+    } else {
+        return None;
+    };
+    let hashes = rest[prefix..].iter().take_while(|&&c| c == b'#').count();
+    (rest.get(prefix + hashes) == Some(&b'"')).then_some((prefix + hashes + 1, hashes))
+}
+
+/// The (), [] and {} of the text outside strings and comments, as
+/// (line, byte offset, bracket) in text order.
+pub(crate) fn scan(lines: &[&str], ext: &str) -> Vec<(usize, usize, u8)> {
+    let comment = line_comment(ext);
+    let block_comments = matches!(ext, "rs" | "js" | "ts" | "c" | "cpp" | "h" | "java" | "go" | "css" | "jsonc");
+    // Single quotes delimit strings except where they are lifetimes or chars
+    let single_quote_strings = matches!(ext, "js" | "ts" | "py" | "json" | "sh" | "rb");
+    let mut found = Vec::new();
+    let mut in_block_comment = false;
+    let mut string: Option<OpenString> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let bytes = line.as_bytes();
+        let mut escaped_newline = false;
+        let mut j = 0;
+        while j < bytes.len() {
+            let b = bytes[j];
+            if in_block_comment {
+                if bytes[j..].starts_with(b"*/") {
+                    in_block_comment = false;
+                    j += 1;
+                }
+            } else if let Some(open) = string {
+                match open {
+                    OpenString::Raw(hashes) => {
+                        if b == b'"' && bytes.get(j + 1..j + 1 + hashes).is_some_and(|h| h.iter().all(|&c| c == b'#')) {
+                            string = None;
+                            j += hashes;
+                        }
+                    }
+                    OpenString::Quoted(_) | OpenString::Triple(_) if b == b'\\' => {
+                        j += 1;
+                        escaped_newline = j == bytes.len();
+                    }
+                    OpenString::Quoted(quote) => {
+                        if b == quote {
+                            string = None;
+                        }
+                    }
+                    OpenString::Triple(quote) => {
+                        if bytes[j..].starts_with(&[quote; 3]) {
+                            string = None;
+                            j += 2;
+                        }
+                    }
+                }
+            } else if comment.is_some_and(|c| bytes[j..].starts_with(c.as_bytes())) {
+                break;
+            } else if block_comments && bytes[j..].starts_with(b"/*") {
+                in_block_comment = true;
+                j += 1;
+            } else if ext == "rs"
+                && (j == 0 || !(bytes[j - 1].is_ascii_alphanumeric() || bytes[j - 1] == b'_'))
+                && let Some((len, hashes)) = raw_string_open(&bytes[j..])
+            {
+                string = Some(OpenString::Raw(hashes));
+                j += len - 1;
+            } else if b == b'\'' && ext == "rs" {
+                // Char literals ('{', '\n', '\u{7b}'); lifetimes have no closing quote
+                let close = match bytes.get(j + 1) {
+                    Some(b'\\') => 3..12,
+                    Some(c) if *c >= 0x80 => 3..6,
+                    _ => 2..3,
+                };
+                if let Some(len) = close.into_iter().find(|&k| bytes.get(j + k) == Some(&b'\'')) {
+                    j += len;
+                }
+            } else if ext == "py" && (b == b'"' || b == b'\'') && bytes[j..].starts_with(&[b; 3]) {
+                string = Some(OpenString::Triple(b));
+                j += 2;
+            } else if b == b'"' || (b == b'\'' && single_quote_strings) || (b == b'`' && matches!(ext, "js" | "ts")) {
+                string = Some(OpenString::Quoted(b));
+            } else if matches!(b, b'(' | b'[' | b'{' | b')' | b']' | b'}') {
+                found.push((i, j, b));
+            }
+            j += 1;
+        }
+        // Other strings end with their line unless the newline is escaped
+        if let Some(OpenString::Quoted(quote)) = string
+            && !escaped_newline
+            && !spans_lines(ext, quote)
+        {
+            string = None;
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brackets(text: &str, ext: &str) -> String {
+        let lines: Vec<&str> = text.lines().collect();
+        scan(&lines, ext).into_iter().map(|(_, _, b)| b as char).collect()
+    }
+
+    #[test]
+    fn rust_char_literals_are_not_brackets() {
+        assert_eq!(brackets("let c = '{'; f(c)", "rs"), "()");
+        assert_eq!(brackets(r"let q = '\''; let b = ['\u{7b}']", "rs"), "[]");
+    }
+
+    #[test]
+    fn rust_lifetimes_do_not_open_a_char() {
+        assert_eq!(brackets("fn f<'a>(x: &'a str) -> &'a [u8] {", "rs"), "()[]{");
+    }
+
+    #[test]
+    fn rust_raw_strings() {
+        assert_eq!(brackets(r##"let s = r#"{ "quoted" }"#; g()"##, "rs"), "()");
+        // Raw strings span lines until their closing quote and hashes
+        assert_eq!(brackets("let s = r#\"\n( \" ]\n\"#;\n[", "rs"), "[");
+    }
+
+    #[test]
+    fn strings_and_line_comments() {
+        assert_eq!(brackets(r#"let s = "}"; // {"#, "rs"), "");
+        assert_eq!(brackets(r#"x = "\"(" # ["#, "py"), "");
+    }
+
+    #[test]
+    fn block_comments() {
+        assert_eq!(brackets("a /* { */ (b)", "rs"), "()");
+        assert_eq!(brackets("/* (\n [ */ {", "c"), "{");
+    }
+
+    #[test]
+    fn python_triple_quoted_strings_span_lines() {
+        let text = "doc = \"\"\"\n  ( unclosed\n\"\"\"\nf(x)\n";
+        assert_eq!(brackets(text, "py"), "()");
+    }
+
+    #[test]
+    fn js_template_strings_span_lines() {
+        assert_eq!(brackets("const t = `\n{ ${x}\n`;\nf()", "js"), "()");
+    }
+
+    #[test]
+    fn stray_closer_flags_only_itself() {
+        let pairs = Brackets::compute("fn f() {\n    g(x));\n}\n", "rs").expect("rust is scanned");
+        assert_eq!(pairs.unbalanced_count(), 1);
+        // The extra ')' is the unbalanced one; the braces still pair up
+        assert_eq!(pairs.on_line(1), &[(5, Some((1, 7))), (7, Some((1, 5))), (8, None)]);
+        assert_eq!(pairs.at((0, 7)), Some(((0, 7), Some((2, 0)))));
+    }
+
+    #[test]
+    fn unknown_languages_are_not_checked() {
+        assert!(Brackets::compute("a (b", "txt").is_none());
+        assert!(Brackets::compute("a (b", "json").is_some());
+    }
+}
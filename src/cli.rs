@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+/// Command-line options understood by the viewer.
+#[derive(Default)]
+pub(crate) struct CliArgs {
+    pub(crate) no_session: bool,
+    /// Files to open side by side in the diff view
+    pub(crate) diff: Option<(PathBuf, PathBuf)>,
+}
+
+pub(crate) fn parse_args() -> CliArgs {
+    let mut args = CliArgs::default();
+    let mut rest = std::env::args().skip(1);
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--no-session" => args.no_session = true,
+            "--diff" => match (rest.next(), rest.next()) {
+                (Some(left), Some(right)) => args.diff = Some((PathBuf::from(left), PathBuf::from(right))),
+                _ => eprintln!("--diff needs two files"),
+            },
+            "-h" | "--help" => {
+                println!("Usage: gemini-file-viewer [--no-session] [--diff <left> <right>]");
+                println!();
+                println!("  --no-session            Start without restoring the previous session");
+                println!("  --diff <left> <right>   Compare two text files line by line");
+                std::process::exit(0);
+            }
+            other => eprintln!("Ignoring unknown argument: {}", other),
+        }
+    }
+    args
+}
@@ -0,0 +1,308 @@
+use eframe::egui;
+use egui::{Color32, ColorImage, Rect, TextureHandle};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use crate::app::{Content, FileViewerApp};
+
+/// How the second image is shown against the current one.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CompareMode {
+    SideBySide,
+    /// Second image right of a movable divider
+    Swipe,
+    /// Second image blended on top
+    Onion,
+    Difference,
+}
+
+impl CompareMode {
+    pub(crate) const ALL: [CompareMode; 4] = [
+        CompareMode::SideBySide,
+        CompareMode::Swipe,
+        CompareMode::Onion,
+        CompareMode::Difference,
+    ];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            CompareMode::SideBySide => "Side by Side",
+            CompareMode::Swipe => "Swipe",
+            CompareMode::Onion => "Onion Skin",
+            CompareMode::Difference => "Difference",
+        }
+    }
+}
+
+/// Result of a per-pixel comparison, aligned at the top-left corner.
+pub(crate) struct DiffStats {
+    pub(crate) mismatched: usize,
+    pub(crate) total: usize,
+    // Smallest rectangle holding every mismatch: [x0, y0, x1, y1), in full-resolution pixels
+    pub(crate) bounds: Option<[usize; 4]>,
+    pub(crate) size_differs: bool,
+    /// Compared at the resolution of the first image's downsampled preview
+    pub(crate) downsampled: bool,
+}
+
+// The diff is recomputed when any of these change
+#[derive(Clone, Copy, PartialEq)]
+struct DiffKey {
+    texture: egui::TextureId,
+    frame: usize,
+    transform: crate::transform::ViewTransform,
+    size: [usize; 2],
+    tolerance: u8,
+}
+
+struct Diff {
+    key: DiffKey,
+    texture: TextureHandle,
+    size: [usize; 2],
+    stats: DiffStats,
+}
+
+/// A second image compared against the one in the main view. Zoom and pan are
+/// shared, so both stay aligned pixel for pixel.
+pub(crate) struct Compare {
+    pub(crate) path: PathBuf,
+    // As decoded, before the first image's rotation/flip
+    source: ColorImage,
+    source_full_size: [usize; 2],
+    // `source` with `transform`, the first image's view rotation/flip, applied
+    pixels: ColorImage,
+    transform: crate::transform::ViewTransform,
+    // Full-resolution size when `pixels` is a downsampled preview
+    full_size: [usize; 2],
+    texture: TextureHandle,
+    pub(crate) mode: CompareMode,
+    // Divider position across the view, 0..1
+    pub(crate) swipe: f32,
+    pub(crate) opacity: f32,
+    // Largest per-channel difference still counted as a match
+    pub(crate) tolerance: u8,
+    diff: Option<Diff>,
+    // Diff being computed on a worker thread; one at a time, so dragging the
+    // tolerance slider only computes the latest value once the last one is done
+    diff_job: Option<(DiffKey, mpsc::Receiver<(ColorImage, DiffStats)>)>,
+}
+
+/// Ask for an image and compare the current one against it.
+pub(crate) fn pick_and_open(app: &mut FileViewerApp, ctx: &egui::Context) {
+    if let Some(path) = crate::io::open_file_dialog() {
+        open(app, ctx, path);
+    }
+}
+
+pub(crate) fn open(app: &mut FileViewerApp, ctx: &egui::Context, path: PathBuf) {
+    let crate::io::ContentKind::Image(format) = crate::io::detect_content_kind(&path) else {
+        app.error_message = Some(format!("Not an image: {}", path.display()));
+        return;
+    };
+    let max_side = ctx.input(|i| i.max_texture_side);
+    match crate::io::load_image(&path, format, max_side) {
+        Ok(mut decoded) if !decoded.frames.is_empty() => {
+            // Animations are compared by their first frame
+            let pixels = decoded.frames.swap_remove(0).pixels;
+            let full_size = decoded.full_size.unwrap_or(pixels.size);
+            let texture = ctx.load_texture(format!("compare:{}", path.display()), pixels.clone(), app.image_filter.texture_options());
+            let previous = app.compare.take();
+            app.compare = Some(Compare {
+                path,
+                source: pixels.clone(),
+                source_full_size: full_size,
+                pixels,
+                transform: Default::default(),
+                full_size,
+                texture,
+                mode: previous.as_ref().map_or(CompareMode::SideBySide, |c| c.mode),
+                swipe: 0.5,
+                opacity: 0.5,
+                tolerance: previous.as_ref().map_or(0, |c| c.tolerance),
+                diff: None,
+                diff_job: None,
+            });
+        }
+        Ok(_) => app.error_message = Some("Image has no frames".to_string()),
+        Err(e) => app.error_message = Some(e),
+    }
+}
+
+/// Rotate and flip the second image like the first one, so both stay aligned.
+fn match_transform(app: &mut FileViewerApp) {
+    let Some(Content::Image(image)) = &app.content else { return };
+    let transform = image.transform;
+    let options = app.image_filter.texture_options();
+    let Some(compare) = &mut app.compare else { return };
+    if compare.transform == transform { return; }
+    compare.pixels = crate::transform::apply_view(compare.source.clone(), transform);
+    let [w, h] = compare.source_full_size;
+    compare.full_size = if transform.quarter_turns % 2 == 1 { [h, w] } else { [w, h] };
+    compare.transform = transform;
+    compare.texture.set(compare.pixels.clone(), options);
+}
+
+/// Re-upload the second image after the filter mode changed.
+pub(crate) fn apply_filter(app: &mut FileViewerApp) {
+    let options = app.image_filter.texture_options();
+    if let Some(compare) = &mut app.compare {
+        compare.texture.set(compare.pixels.clone(), options);
+    }
+}
+
+/// Start computing the difference image and stats if the inputs changed since
+/// last time, and pick up a finished one. The previous diff stays shown meanwhile.
+pub(crate) fn ensure_diff(app: &mut FileViewerApp, ctx: &egui::Context) {
+    let Some(Content::Image(image)) = &app.content else { return };
+    let Some(compare) = &mut app.compare else { return };
+    if let Some((job_key, rx)) = &compare.diff_job {
+        match rx.try_recv() {
+            Ok((pixels, stats)) => {
+                let size = pixels.size;
+                let texture = ctx.load_texture("compare-diff", pixels, egui::TextureOptions::NEAREST);
+                compare.diff = Some(Diff { key: *job_key, texture, size, stats });
+                compare.diff_job = None;
+            }
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => compare.diff_job = None,
+        }
+    }
+    let key = DiffKey {
+        texture: image.texture.id(),
+        frame: image.current_frame,
+        transform: image.transform,
+        size: image.pixels().size,
+        tolerance: compare.tolerance,
+    };
+    if compare.diff.as_ref().is_some_and(|d| d.key == key) { return; }
+    let display = image.display_size();
+    let a = (image.pixels().clone(), [display.x, display.y]);
+    let b = (compare.pixels.clone(), compare.full_size.map(|v| v as f32));
+    let tolerance = compare.tolerance;
+    let (tx, rx) = mpsc::channel();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        let _ = tx.send(compute_diff((&a.0, a.1), (&b.0, b.1), tolerance));
+        ctx.request_repaint();
+    });
+    compare.diff_job = Some((key, rx));
+}
+
+pub(crate) fn diff_stats(app: &FileViewerApp) -> Option<&DiffStats> {
+    app.compare.as_ref()?.diff.as_ref().map(|d| &d.stats)
+}
+
+// Mismatches in red (brighter for larger differences), pixels covered by only one
+// image in magenta, and matching pixels as a dim gray copy of the first image.
+// Each image comes with its full-resolution size; both are sampled on the grid of
+// the first one's pixels, so downsampled previews line up by image position.
+fn compute_diff(a: (&ColorImage, [f32; 2]), b: (&ColorImage, [f32; 2]), tolerance: u8) -> (ColorImage, DiffStats) {
+    let ((a, a_full), (b, b_full)) = (a, b);
+    // Full-resolution pixels per preview pixel, per axis
+    let a_step = [a_full[0] / a.size[0].max(1) as f32, a_full[1] / a.size[1].max(1) as f32];
+    let b_step = [b_full[0] / b.size[0].max(1) as f32, b_full[1] / b.size[1].max(1) as f32];
+    let size = [
+        a.size[0].max((b_full[0] / a_step[0]).ceil() as usize),
+        a.size[1].max((b_full[1] / a_step[1]).ceil() as usize),
+    ];
+    let mut pixels = Vec::with_capacity(size[0] * size[1]);
+    let mut stats = DiffStats {
+        mismatched: 0,
+        total: size[0] * size[1],
+        bounds: None,
+        size_differs: a_full.map(f32::round) != b_full.map(f32::round),
+        downsampled: a_step != [1.0, 1.0],
+    };
+    let at = |img: &ColorImage, x: usize, y: usize| {
+        (x < img.size[0] && y < img.size[1]).then(|| img.pixels[y * img.size[0] + x])
+    };
+    // Pixel of the second image under the middle of a grid cell
+    let b_at = |x: usize, y: usize| {
+        let (fx, fy) = ((x as f32 + 0.5) * a_step[0], (y as f32 + 0.5) * a_step[1]);
+        (fx < b_full[0] && fy < b_full[1]).then(|| at(b, (fx / b_step[0]) as usize, (fy / b_step[1]) as usize)).flatten()
+    };
+    for y in 0..size[1] {
+        for x in 0..size[0] {
+            let (color, mismatch) = match (at(a, x, y), b_at(x, y)) {
+                (Some(pa), Some(pb)) => {
+                    let delta = pa.to_array().iter().zip(pb.to_array()).map(|(p, q)| p.abs_diff(q)).max().unwrap_or(0);
+                    if delta > tolerance {
+                        (Color32::from_rgb(110 + (delta as u16 * 145 / 255) as u8, 0, 0), true)
+                    } else {
+                        let luma = (pa.r() as u16 * 3 + pa.g() as u16 * 6 + pa.b() as u16) / 10;
+                        (Color32::from_gray((luma / 3) as u8), false)
+                    }
+                }
+                _ => (Color32::from_rgb(255, 0, 255), true),
+            };
+            if mismatch {
+                stats.mismatched += 1;
+                let bounds = stats.bounds.get_or_insert([x, y, x + 1, y + 1]);
+                bounds[0] = bounds[0].min(x);
+                bounds[1] = bounds[1].min(y);
+                bounds[2] = bounds[2].max(x + 1);
+                bounds[3] = bounds[3].max(y + 1);
+            }
+            pixels.push(color);
+        }
+    }
+    stats.bounds = stats.bounds.map(|[x0, y0, x1, y1]| {
+        let to_full = |v: usize, axis: usize| ((v as f32 * a_step[axis]).round() as usize).min(a_full[axis].max(b_full[axis]).round() as usize);
+        [to_full(x0, 0), to_full(y0, 1), to_full(x1, 0), to_full(y1, 1)]
+    });
+    (ColorImage { size, pixels }, stats)
+}
+
+/// Draw the second image (or the difference) for the current mode. `view` is
+/// the part of `rect` holding the first image and `image_rect` its placement.
+pub(crate) fn paint(ui: &egui::Ui, app: &mut FileViewerApp, rect: Rect, view: Rect, image_rect: Rect) {
+    match_transform(app);
+    let Some(Content::Image(image)) = &app.content else { return };
+    // Screen size of one full-resolution pixel of the first image; the second is
+    // drawn at the same scale, and the diff at the scale of the first one's preview
+    let px_scale = image_rect.width() / image.display_size().x.max(1.0);
+    let diff_scale = image_rect.width() / image.pixels().size[0].max(1) as f32;
+    if app.compare.as_ref().is_some_and(|c| c.mode == CompareMode::Difference) {
+        ensure_diff(app, ui.ctx());
+    }
+    let Some(compare) = &app.compare else { return };
+    let (texture, mode, swipe, opacity) = (compare.texture.id(), compare.mode, compare.swipe, compare.opacity);
+    let size = egui::vec2(compare.full_size[0] as f32, compare.full_size[1] as f32) * px_scale;
+    let diff = compare.diff.as_ref().map(|d| (d.texture.id(), d.size, d.stats.bounds));
+    let uv = Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+    let divider = egui::Stroke::new(2.0, Color32::WHITE);
+
+    match mode {
+        CompareMode::SideBySide => {
+            let right = Rect::from_min_max(egui::pos2(view.max.x, rect.min.y), rect.max);
+            let painter = ui.painter_at(right);
+            let second = Rect::from_min_size(image_rect.min + egui::vec2(view.width(), 0.0), size);
+            crate::image_view::paint_transparency_bg(ui, app, &painter, second);
+            painter.image(texture, second, uv, Color32::WHITE);
+            ui.painter_at(rect).vline(view.max.x, rect.y_range(), divider);
+        }
+        CompareMode::Swipe => {
+            let x = rect.min.x + swipe * rect.width();
+            let painter = ui.painter_at(Rect::from_min_max(egui::pos2(x, rect.min.y), rect.max));
+            let second = Rect::from_min_size(image_rect.min, size);
+            crate::image_view::paint_transparency_bg(ui, app, &painter, second);
+            painter.image(texture, second, uv, Color32::WHITE);
+            ui.painter_at(rect).vline(x, rect.y_range(), divider);
+        }
+        CompareMode::Onion => {
+            let second = Rect::from_min_size(image_rect.min, size);
+            let tint = Color32::from_white_alpha((opacity.clamp(0.0, 1.0) * 255.0) as u8);
+            ui.painter_at(rect).image(texture, second, uv, tint);
+        }
+        CompareMode::Difference => {
+            let Some((diff_texture, diff_size, bounds)) = diff else { return };
+            let painter = ui.painter_at(rect);
+            let diff_rect = Rect::from_min_size(image_rect.min, egui::vec2(diff_size[0] as f32, diff_size[1] as f32) * diff_scale);
+            painter.image(diff_texture, diff_rect, uv, Color32::WHITE);
+            if let Some([x0, y0, x1, y1]) = bounds {
+                let min = image_rect.min + egui::vec2(x0 as f32, y0 as f32) * px_scale;
+                let max = image_rect.min + egui::vec2(x1 as f32, y1 as f32) * px_scale;
+                painter.rect_stroke(Rect::from_min_max(min, max), 0.0, egui::Stroke::new(1.5, Color32::YELLOW), egui::StrokeKind::Outside);
+            }
+        }
+    }
+}
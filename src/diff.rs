@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+// Edit distance beyond which the remaining region is shown as one replaced block;
+// keeps time and memory bounded for unrelated files
+const MAX_LINE_EDITS: usize = 2_000;
+const MAX_CHAR_EDITS: usize = 200;
+// Lines longer than this are highlighted as a whole instead of per character
+const MAX_INTRA_LINE_CHARS: usize = 2_000;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// How a row of the diff relates the two files.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RowKind {
+    Same,
+    /// Paired lines that differ; intra-line ranges say where
+    Changed,
+    Removed,
+    Added,
+}
+
+/// One aligned row: a line from either side (or both), with changed byte ranges.
+pub(crate) struct DiffRow {
+    pub(crate) kind: RowKind,
+    pub(crate) left: Option<usize>,
+    pub(crate) right: Option<usize>,
+    pub(crate) left_ranges: Vec<Range<usize>>,
+    pub(crate) right_ranges: Vec<Range<usize>>,
+}
+
+/// Line diff of two text files, aligned into rows for side-by-side and inline views.
+pub(crate) struct TextDiff {
+    pub(crate) left_path: PathBuf,
+    pub(crate) right_path: PathBuf,
+    pub(crate) left: Vec<String>,
+    pub(crate) right: Vec<String>,
+    pub(crate) rows: Vec<DiffRow>,
+    // First row of each block of consecutive changed rows
+    pub(crate) changes: Vec<usize>,
+    pub(crate) current: usize,
+    pub(crate) inline: bool,
+    pub(crate) scroll_to_current: bool,
+    /// Syntax state at the start of each left and right line, filled in by the view
+    pub(crate) line_states: Option<[Vec<crate::diff_view::SideState>; 2]>,
+}
+
+impl TextDiff {
+    pub(crate) fn open(left_path: &Path, right_path: &Path) -> Result<Self, String> {
+        let (left, _, _) = crate::io::load_text(left_path)?;
+        let (right, _, _) = crate::io::load_text(right_path)?;
+        Ok(Self::from_texts(left_path, &left, right_path, &right))
+    }
+
+    /// Diff two texts already in memory; the paths are used for titles and syntax.
+    pub(crate) fn from_texts(left_path: &Path, left: &str, right_path: &Path, right: &str) -> Self {
+        let left: Vec<String> = left.lines().map(str::to_string).collect();
+        let right: Vec<String> = right.lines().map(str::to_string).collect();
+        let rows = align(&left, &right);
+        let changes = rows
+            .iter()
+            .enumerate()
+            .filter(|(i, row)| row.kind != RowKind::Same && (*i == 0 || rows[i - 1].kind == RowKind::Same))
+            .map(|(i, _)| i)
+            .collect();
+        Self {
+            left_path: left_path.to_path_buf(),
+            right_path: right_path.to_path_buf(),
+            left,
+            right,
+            rows,
+            changes,
+            current: 0,
+            inline: false,
+            scroll_to_current: true,
+            line_states: None,
+        }
+    }
+
+    /// Move to the next or previous change, wrapping around.
+    pub(crate) fn step(&mut self, forward: bool) {
+        let count = self.changes.len();
+        if count == 0 { return; }
+        self.current = if forward { (self.current + 1) % count } else { (self.current + count - 1) % count };
+        self.scroll_to_current = true;
+    }
+}
+
+// Myers' O(ND) diff over interned tokens. Returns None when the edit distance
+// exceeds `max_edits`.
+fn myers(a: &[u32], b: &[u32], max_edits: usize) -> Option<Vec<Op>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let mut v = vec![0isize; 2 * max + 3];
+    // Snapshot of v[-d-1..=d+1] taken before each round d, for the backtrack
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    for d in 0..=max.min(max_edits) as isize {
+        trace.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let i = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) { v[i + 1] } else { v[i - 1] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[i] = x;
+            if x >= n && y >= m {
+                return Some(backtrack(&trace, n, m));
+            }
+        }
+    }
+    None
+}
+
+fn backtrack(trace: &[Vec<isize>], n: isize, m: isize) -> Vec<Op> {
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| v[(k + d + 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+        let prev_x = if d == 0 { 0 } else { at(prev_k) };
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            ops.push(Op::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            ops.push(if x == prev_x { Op::Insert } else { Op::Delete });
+            x = prev_x;
+            y = prev_y;
+        }
+    }
+    ops.reverse();
+    ops
+}
+
+// Diff ops with the common prefix and suffix handled up front, falling back to
+// delete-all/insert-all for the middle when it is too different
+fn diff_ops(a: &[u32], b: &[u32], max_edits: usize) -> Vec<Op> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let (mid_a, mid_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    let mut ops = vec![Op::Equal; prefix];
+    match myers(mid_a, mid_b, max_edits) {
+        Some(mid) => ops.extend(mid),
+        None => {
+            ops.extend(std::iter::repeat_n(Op::Delete, mid_a.len()));
+            ops.extend(std::iter::repeat_n(Op::Insert, mid_b.len()));
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Equal, suffix));
+    ops
+}
+
+pub(crate) fn align<'a>(left: &'a [String], right: &'a [String]) -> Vec<DiffRow> {
+    let mut ids: HashMap<&str, u32> = HashMap::new();
+    let mut intern = |line: &'a String| {
+        let next = ids.len() as u32;
+        *ids.entry(line.as_str()).or_insert(next)
+    };
+    let a: Vec<u32> = left.iter().map(&mut intern).collect();
+    let b: Vec<u32> = right.iter().map(&mut intern).collect();
+    let ops = diff_ops(&a, &b, MAX_LINE_EDITS);
+
+    let mut rows = Vec::new();
+    let (mut li, mut ri) = (0, 0);
+    let mut i = 0;
+    while i < ops.len() {
+        if ops[i] == Op::Equal {
+            rows.push(DiffRow { kind: RowKind::Same, left: Some(li), right: Some(ri), left_ranges: Vec::new(), right_ranges: Vec::new() });
+            li += 1;
+            ri += 1;
+            i += 1;
+            continue;
+        }
+        // A run of deletes and inserts: pair them up as changed lines, the rest stand alone
+        let run = ops[i..].iter().take_while(|op| **op != Op::Equal).count();
+        let deleted = ops[i..i + run].iter().filter(|op| **op == Op::Delete).count();
+        let inserted = run - deleted;
+        for j in 0..deleted.max(inserted) {
+            let left_line = (j < deleted).then_some(li + j);
+            let right_line = (j < inserted).then_some(ri + j);
+            let mut row = DiffRow { kind: RowKind::Changed, left: left_line, right: right_line, left_ranges: Vec::new(), right_ranges: Vec::new() };
+            match (left_line, right_line) {
+                (Some(l), Some(r)) => (row.left_ranges, row.right_ranges) = intra_line(&left[l], &right[r]),
+                (Some(_), None) => row.kind = RowKind::Removed,
+                _ => row.kind = RowKind::Added,
+            }
+            rows.push(row);
+        }
+        li += deleted;
+        ri += inserted;
+        i += run;
+    }
+    rows
+}
+
+// Byte ranges that differ between two paired lines
+fn intra_line(left: &str, right: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    if left.len() > MAX_INTRA_LINE_CHARS || right.len() > MAX_INTRA_LINE_CHARS {
+        return (std::iter::once(0..left.len()).collect(), std::iter::once(0..right.len()).collect());
+    }
+    let a: Vec<(usize, char)> = left.char_indices().collect();
+    let b: Vec<(usize, char)> = right.char_indices().collect();
+    let ops = diff_ops(
+        &a.iter().map(|(_, c)| *c as u32).collect::<Vec<_>>(),
+        &b.iter().map(|(_, c)| *c as u32).collect::<Vec<_>>(),
+        MAX_CHAR_EDITS,
+    );
+    let (mut left_ranges, mut right_ranges): (Vec<Range<usize>>, Vec<Range<usize>>) = (Vec::new(), Vec::new());
+    let push = |ranges: &mut Vec<Range<usize>>, range: Range<usize>| match ranges.last_mut() {
+        Some(last) if last.end == range.start => last.end = range.end,
+        _ => ranges.push(range),
+    };
+    let (mut ai, mut bi) = (0, 0);
+    for op in ops {
+        match op {
+            Op::Equal => {
+                ai += 1;
+                bi += 1;
+            }
+            Op::Delete => {
+                let (start, c) = a[ai];
+                push(&mut left_ranges, start..start + c.len_utf8());
+                ai += 1;
+            }
+            Op::Insert => {
+                let (start, c) = b[bi];
+                push(&mut right_ranges, start..start + c.len_utf8());
+                bi += 1;
+            }
+        }
+    }
+    (left_ranges, right_ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Op::{Delete as D, Equal as E, Insert as I};
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn myers_empty_inputs() {
+        assert_eq!(myers(&[], &[], 10), Some(vec![]));
+        assert_eq!(myers(&[], &[1, 2], 10), Some(vec![I, I]));
+        assert_eq!(myers(&[1, 2], &[], 10), Some(vec![D, D]));
+    }
+
+    #[test]
+    fn myers_finds_shortest_script() {
+        assert_eq!(myers(&[1, 2, 3], &[1, 3], 10), Some(vec![E, D, E]));
+        assert_eq!(myers(&[1, 3], &[1, 2, 3], 10), Some(vec![E, I, E]));
+        assert_eq!(myers(&[1, 2], &[3, 4], 1), None);
+    }
+
+    #[test]
+    fn diff_ops_prefix_and_suffix_only() {
+        assert_eq!(diff_ops(&[1, 2, 3], &[1, 2], 10), vec![E, E, D]);
+        assert_eq!(diff_ops(&[1, 2], &[0, 1, 2], 10), vec![I, E, E]);
+        assert_eq!(diff_ops(&[1, 2], &[1, 2], 0), vec![E, E]);
+    }
+
+    #[test]
+    fn diff_ops_falls_back_over_the_limit() {
+        assert_eq!(diff_ops(&[0, 1, 2, 9], &[0, 3, 4, 9], 1), vec![E, D, D, I, I, E]);
+    }
+
+    #[test]
+    fn align_empty_sides() {
+        assert!(align(&[], &[]).is_empty());
+        let rows = align(&[], &lines(&["x"]));
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].kind == RowKind::Added && rows[0].left.is_none() && rows[0].right == Some(0));
+        let rows = align(&lines(&["x"]), &[]);
+        assert!(rows[0].kind == RowKind::Removed && rows[0].left == Some(0) && rows[0].right.is_none());
+    }
+
+    #[test]
+    fn align_pairs_changed_lines() {
+        let rows = align(&lines(&["a", "old line", "b", "gone"]), &lines(&["a", "new line", "b"]));
+        let kinds: Vec<RowKind> = rows.iter().map(|r| r.kind).collect();
+        assert!(kinds == [RowKind::Same, RowKind::Changed, RowKind::Same, RowKind::Removed]);
+        assert_eq!((rows[1].left, rows[1].right), (Some(1), Some(1)));
+        assert_eq!(rows[1].left_ranges, vec![0..3]);
+        assert_eq!(rows[1].right_ranges, vec![0..3]);
+    }
+}
@@ -0,0 +1,196 @@
+use eframe::egui;
+use egui::text::LayoutJob;
+use egui::{Color32, FontId, TextFormat};
+use std::ops::Range;
+use std::path::Path;
+use crate::app::FileViewerApp;
+use crate::diff::{RowKind, TextDiff};
+
+const REMOVED_BG: Color32 = Color32::from_rgba_premultiplied(56, 20, 20, 40);
+const REMOVED_STRONG: Color32 = Color32::from_rgba_premultiplied(150, 45, 45, 110);
+const ADDED_BG: Color32 = Color32::from_rgba_premultiplied(20, 50, 25, 40);
+const ADDED_STRONG: Color32 = Color32::from_rgba_premultiplied(40, 120, 55, 110);
+
+/// Open a diff of two text files in place of the main view.
+pub(crate) fn open(app: &mut FileViewerApp, left: &Path, right: &Path) {
+    match TextDiff::open(left, right) {
+        Ok(diff) => {
+            app.text_diff = Some(diff);
+            app.error_message = None;
+        }
+        Err(e) => app.error_message = Some(e),
+    }
+}
+
+/// Diff the committed version of the current file (left) against the working copy.
+pub(crate) fn open_head(app: &mut FileViewerApp) {
+    let (Some(path), Some(crate::app::Content::Text(text)), Some(status)) = (&app.current_path, &app.content, &app.git_status) else { return };
+    let (Some(head_text), Some(rel)) = (&status.head_text, &status.rel_path) else { return };
+    // Named like git's `HEAD:<path>` so the title and syntax follow the file
+    let head_path = Path::new(&format!("HEAD:{}", rel.display())).to_path_buf();
+    app.text_diff = Some(TextDiff::from_texts(&head_path, head_text, path, text));
+    app.error_message = None;
+}
+
+/// Ask for a second file and diff the current one against it.
+pub(crate) fn compare_with(app: &mut FileViewerApp) {
+    if let Some(left) = app.current_path.clone()
+        && let Some(right) = crate::io::open_file_dialog()
+    {
+        open(app, &left, &right);
+    }
+}
+
+/// Syntax state carried from line to line on one side of the diff.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct SideState {
+    depth: i32,
+    in_block_comment: bool,
+}
+
+// The state before each line, so rows can be drawn starting anywhere
+fn line_states(lines: &[String], ext: &str, style: &Style) -> Vec<SideState> {
+    let mut state = SideState::default();
+    let mut states = Vec::with_capacity(lines.len());
+    for line in lines {
+        states.push(state);
+        let (mut job, mut counter) = (LayoutJob::default(), 0);
+        crate::highlight::append_highlighted(&mut job, line, ext, "", style.font_id.clone(), style.text_color, style.do_syntax, &mut state.depth, 0, &mut counter, &mut state.in_block_comment);
+    }
+    states
+}
+
+struct Style {
+    font_id: FontId,
+    text_color: Color32,
+    do_syntax: bool,
+}
+
+// Gutter plus syntax-highlighted line, with changed byte ranges given a stronger background
+fn line_job(line: &str, gutter: &str, ranges: &[Range<usize>], strong: Color32, ext: &str, style: &Style, state: SideState) -> LayoutJob {
+    let mut state = state;
+    let mut job = LayoutJob::default();
+    job.append(gutter, 0.0, TextFormat { font_id: style.font_id.clone(), color: Color32::GRAY, ..Default::default() });
+    let start = job.text.len();
+    let mut counter = 0;
+    crate::highlight::append_highlighted(&mut job, line, ext, "", style.font_id.clone(), style.text_color, style.do_syntax, &mut state.depth, 0, &mut counter, &mut state.in_block_comment);
+    for range in ranges {
+        crate::highlight::restyle(&mut job, start + range.start..start + range.end, |f| f.background = strong);
+    }
+    job
+}
+
+fn paint_cell(ui: &mut egui::Ui, job: LayoutJob, width: f32, height: f32, bg: Option<Color32>) -> egui::Rect {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(width, height), egui::Sense::hover());
+    if let Some(bg) = bg {
+        ui.painter().rect_filled(rect, 0.0, bg);
+    }
+    let galley = ui.fonts(|f| f.layout_job(job));
+    ui.painter_at(rect).galley(rect.min, galley, Color32::WHITE);
+    rect
+}
+
+/// Draw the diff (side by side or inline) and handle change navigation keys.
+pub(crate) fn show(ui: &mut egui::Ui, app: &mut FileViewerApp) {
+    let mut font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    font_id.size = (font_id.size * app.text_zoom).clamp(8.0, 48.0);
+    let text_color = ui.visuals().text_color();
+    let Some(diff) = &mut app.text_diff else { return };
+
+    if !ui.ctx().wants_keyboard_input() {
+        let (next, prev, close) = ui.input(|i| (
+            i.modifiers.alt && i.key_pressed(egui::Key::ArrowDown),
+            i.modifiers.alt && i.key_pressed(egui::Key::ArrowUp),
+            i.key_pressed(egui::Key::Escape),
+        ));
+        if next { diff.step(true); }
+        if prev { diff.step(false); }
+        if close {
+            app.text_diff = None;
+            return;
+        }
+    }
+
+    let total_len: usize = diff.left.iter().chain(&diff.right).map(String::len).sum();
+    let style = Style { font_id: font_id.clone(), text_color, do_syntax: total_len <= crate::app::HIGHLIGHT_CHAR_THRESHOLD };
+    let ext_of = |p: &Path| p.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    let (left_ext, right_ext) = (ext_of(&diff.left_path), ext_of(&diff.right_path));
+    let row_height = ui.fonts(|f| f.row_height(&font_id));
+    let char_width = ui.fonts(|f| f.glyph_width(&font_id, 'M'));
+    let longest = diff.left.iter().chain(&diff.right).map(|l| l.chars().count()).max().unwrap_or(0);
+    if style.do_syntax && diff.line_states.is_none() {
+        diff.line_states = Some([line_states(&diff.left, &left_ext, &style), line_states(&diff.right, &right_ext, &style)]);
+    }
+    let state_at = |side: usize, line: usize| diff.line_states.as_ref().map_or_else(SideState::default, |s| s[side][line]);
+    // Inline view: a changed row takes one line per side it has
+    let lines: Vec<(usize, bool)> = if diff.inline {
+        diff.rows
+            .iter()
+            .enumerate()
+            .flat_map(|(index, row)| {
+                let left = row.left.is_some().then_some((index, false));
+                let right = (row.right.is_some() && (row.kind != RowKind::Same || row.left.is_none())).then_some((index, true));
+                left.into_iter().chain(right)
+            })
+            .collect()
+    } else {
+        (0..diff.rows.len()).map(|index| (index, false)).collect()
+    };
+    let mut area = egui::ScrollArea::both().auto_shrink([false, false]);
+    if std::mem::take(&mut diff.scroll_to_current)
+        && let Some(&target_row) = diff.changes.get(diff.current)
+    {
+        let line = lines.partition_point(|&(index, _)| index < target_row);
+        area = area.vertical_scroll_offset((line as f32 * row_height - ui.available_height() / 2.0).max(0.0));
+    }
+
+    ui.scope(|ui| {
+        ui.spacing_mut().item_spacing.y = 0.0;
+        area.show_rows(ui, row_height, lines.len(), |ui, range| {
+            if diff.inline {
+                let width = (ui.available_width()).max((longest + 14) as f32 * char_width);
+                let number = |n: Option<usize>| n.map_or(String::new(), |n| (n + 1).to_string());
+                for &(index, right) in &lines[range] {
+                    let row = &diff.rows[index];
+                    let job = match (right, row.left, row.right) {
+                        (false, Some(l), _) => {
+                            let marker = if row.kind == RowKind::Same { ' ' } else { '-' };
+                            let gutter = format!("{:>5} {:>5} {} ", number(row.left), if row.kind == RowKind::Same { number(row.right) } else { String::new() }, marker);
+                            line_job(&diff.left[l], &gutter, &row.left_ranges, REMOVED_STRONG, &left_ext, &style, state_at(0, l))
+                        }
+                        (_, _, Some(r)) => {
+                            let gutter = format!("{:>5} {:>5} + ", "", number(row.right));
+                            line_job(&diff.right[r], &gutter, &row.right_ranges, ADDED_STRONG, &right_ext, &style, state_at(1, r))
+                        }
+                        _ => LayoutJob::default(),
+                    };
+                    let bg = match (row.kind, right) {
+                        (RowKind::Same, _) => None,
+                        (_, false) => Some(REMOVED_BG),
+                        (_, true) => Some(ADDED_BG),
+                    };
+                    paint_cell(ui, job, width, row_height, bg);
+                }
+            } else {
+                let half = ((ui.available_width() - 8.0) / 2.0).max((longest + 7) as f32 * char_width);
+                for &(index, _) in &lines[range] {
+                    let row = &diff.rows[index];
+                    let changed = row.kind != RowKind::Same;
+                    ui.horizontal(|ui| {
+                        let left_job = match row.left {
+                            Some(l) => line_job(&diff.left[l], &format!("{:>5} ", l + 1), &row.left_ranges, REMOVED_STRONG, &left_ext, &style, state_at(0, l)),
+                            None => LayoutJob::default(),
+                        };
+                        paint_cell(ui, left_job, half, row_height, changed.then_some(REMOVED_BG).filter(|_| row.left.is_some()));
+                        ui.add_space(8.0);
+                        let right_job = match row.right {
+                            Some(r) => line_job(&diff.right[r], &format!("{:>5} ", r + 1), &row.right_ranges, ADDED_STRONG, &right_ext, &style, state_at(1, r)),
+                            None => LayoutJob::default(),
+                        };
+                        paint_cell(ui, right_job, half, row_height, changed.then_some(ADDED_BG).filter(|_| row.right.is_some()));
+                    });
+                }
+            }
+        });
+    });
+}
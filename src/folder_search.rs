@@ -0,0 +1,457 @@
+use eframe::egui;
+use egui::text::LayoutJob;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use crate::app::FileViewerApp;
+use crate::search::Matcher;
+
+// Files bigger than the viewer would open are skipped
+const MAX_SEARCH_FILE_BYTES: u64 = 10_000_000;
+// Searching stops once this many lines have matched
+const MAX_TOTAL_HITS: usize = 5_000;
+const MAX_PREVIEW_BYTES: usize = 300;
+
+/// A matching line, with the index of its first match among all matches in the
+/// file so the find bar can select it.
+pub(crate) struct LineHit {
+    pub(crate) line: usize,
+    pub(crate) preview: String,
+    pub(crate) match_index: usize,
+}
+
+pub(crate) struct FileHits {
+    pub(crate) path: PathBuf,
+    pub(crate) hits: Vec<LineHit>,
+}
+
+/// "Find in folder": a directory walk honoring .gitignore files, with the files
+/// searched on worker threads. Dropping it cancels the search.
+pub(crate) struct FolderSearch {
+    pub(crate) root: PathBuf,
+    pub(crate) query: String,
+    // Query the current results are for
+    pub(crate) searched: String,
+    pub(crate) results: Vec<FileHits>,
+    pub(crate) total_hits: usize,
+    pub(crate) truncated: bool,
+    pub(crate) focus_query: bool,
+    scanned: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+    rx: Option<mpsc::Receiver<FileHits>>,
+}
+
+impl Drop for FolderSearch {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl FolderSearch {
+    pub(crate) fn new(root: PathBuf, query: String) -> Self {
+        Self {
+            // Canonical, so ignore rules and result paths line up with the repository's
+            root: fs::canonicalize(&root).unwrap_or(root),
+            query,
+            searched: String::new(),
+            results: Vec::new(),
+            total_hits: 0,
+            truncated: false,
+            focus_query: true,
+            scanned: Arc::new(AtomicUsize::new(0)),
+            cancel: Arc::new(AtomicBool::new(false)),
+            rx: None,
+        }
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    pub(crate) fn scanned(&self) -> usize {
+        self.scanned.load(Ordering::Relaxed)
+    }
+
+    /// Cancel any running search and start over with the current query and root.
+    pub(crate) fn start(&mut self, ctx: &egui::Context) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.results.clear();
+        self.total_hits = 0;
+        self.truncated = false;
+        self.searched = self.query.clone();
+        self.scanned = Arc::new(AtomicUsize::new(0));
+        self.cancel = Arc::new(AtomicBool::new(false));
+        self.rx = Matcher::new(&self.query)
+            .map(|matcher| spawn_search(ctx.clone(), self.root.clone(), matcher, self.scanned.clone(), self.cancel.clone()));
+    }
+
+    /// Collect results that arrived since the last frame.
+    pub(crate) fn receive(&mut self) {
+        let Some(rx) = &self.rx else { return };
+        loop {
+            match rx.try_recv() {
+                Ok(file) => {
+                    self.total_hits += file.hits.len();
+                    let at = self.results.partition_point(|f| f.path < file.path);
+                    self.results.insert(at, file);
+                    if self.total_hits >= MAX_TOTAL_HITS {
+                        self.truncated = true;
+                        self.cancel.store(true, Ordering::Relaxed);
+                        self.rx = None;
+                        return;
+                    }
+                }
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.rx = None;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// Walk on one thread and search on the others; the channel closes when every worker is done
+fn spawn_search(ctx: egui::Context, root: PathBuf, matcher: Matcher, scanned: Arc<AtomicUsize>, cancel: Arc<AtomicBool>) -> mpsc::Receiver<FileHits> {
+    let (tx, rx) = mpsc::channel();
+    let (path_tx, path_rx) = mpsc::channel::<PathBuf>();
+    let path_rx = Arc::new(Mutex::new(path_rx));
+    let matcher = Arc::new(matcher);
+    let workers = std::thread::available_parallelism().map_or(2, |n| n.get()).clamp(1, 8);
+    for _ in 0..workers {
+        let (tx, path_rx, matcher, scanned, cancel, ctx) = (tx.clone(), path_rx.clone(), matcher.clone(), scanned.clone(), cancel.clone(), ctx.clone());
+        std::thread::spawn(move || {
+            loop {
+                let next = path_rx.lock().ok().and_then(|rx| rx.recv().ok());
+                let Some(path) = next else { return };
+                if cancel.load(Ordering::Relaxed) { return; }
+                let hits = search_file(&path, &matcher);
+                scanned.fetch_add(1, Ordering::Relaxed);
+                if let Some(hits) = hits {
+                    if tx.send(FileHits { path, hits }).is_err() { return; }
+                    ctx.request_repaint();
+                }
+            }
+        });
+    }
+    std::thread::spawn(move || {
+        walk(&root, &path_tx, &cancel);
+        drop(path_tx);
+        ctx.request_repaint();
+    });
+    rx
+}
+
+// Matching lines of a text file; None for binary, unreadable or matchless files
+fn search_file(path: &Path, matcher: &Matcher) -> Option<Vec<LineHit>> {
+    if fs::metadata(path).ok()?.len() > MAX_SEARCH_FILE_BYTES { return None; }
+    let bytes = fs::read(path).ok()?;
+    if bytes[..bytes.len().min(8192)].contains(&0) { return None; }
+    let text = String::from_utf8_lossy(&bytes);
+    if !matcher.is_match(&text) { return None; }
+    let mut hits = Vec::new();
+    let mut match_index = 0;
+    for (line, content) in text.lines().enumerate() {
+        let found = matcher.find_iter(content).len();
+        if found > 0 {
+            hits.push(LineHit { line, preview: preview(content), match_index });
+            match_index += found;
+        }
+    }
+    (!hits.is_empty()).then_some(hits)
+}
+
+fn preview(line: &str) -> String {
+    let line = line.trim();
+    if line.len() <= MAX_PREVIEW_BYTES { return line.to_string(); }
+    let mut end = MAX_PREVIEW_BYTES;
+    while !line.is_char_boundary(end) { end -= 1; }
+    format!("{}…", &line[..end])
+}
+
+/// One .gitignore (or info/exclude) line, relative to the directory it came from.
+#[derive(Clone)]
+struct IgnoreRule {
+    base: PathBuf,
+    pattern: String,
+    negate: bool,
+    dir_only: bool,
+    // Patterns with a slash match the whole relative path, others just the name
+    anchored: bool,
+}
+
+fn read_ignore_file(file: &Path, base: &Path) -> Vec<IgnoreRule> {
+    let Ok(text) = fs::read_to_string(file) else { return Vec::new() };
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') { return None; }
+            let (negate, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let anchored = line.contains('/');
+            let pattern = line.trim_start_matches('/').to_string();
+            (!pattern.is_empty()).then(|| IgnoreRule { base: base.to_path_buf(), pattern, negate, dir_only, anchored })
+        })
+        .collect()
+}
+
+fn is_ignored(rules: &[IgnoreRule], path: &Path, is_dir: bool) -> bool {
+    let mut ignored = false;
+    for rule in rules {
+        if rule.dir_only && !is_dir { continue; }
+        let Ok(rel) = path.strip_prefix(&rule.base) else { continue };
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        let subject = if rule.anchored { rel.as_str() } else { rel.rsplit('/').next().unwrap_or(&rel) };
+        if glob_match(rule.pattern.as_bytes(), subject.as_bytes()) {
+            ignored = !rule.negate;
+        }
+    }
+    ignored
+}
+
+// Gitignore-style glob: `*` and `?` stay within a path component, `**` crosses them
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => {
+            let rest = pattern[2..].strip_prefix(b"/").unwrap_or(&pattern[2..]);
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        Some(b'*') => {
+            let rest = &pattern[1..];
+            let limit = text.iter().position(|b| *b == b'/').unwrap_or(text.len());
+            (0..=limit).any(|i| glob_match(rest, &text[i..]))
+        }
+        Some(b'?') => text.first().is_some_and(|b| *b != b'/') && glob_match(&pattern[1..], &text[1..]),
+        Some(b'[') => {
+            let Some(close) = pattern.iter().skip(2).position(|b| *b == b']').map(|p| p + 2) else {
+                return text.first() == Some(&b'[') && glob_match(&pattern[1..], &text[1..]);
+            };
+            let Some(&c) = text.first() else { return false };
+            let class = &pattern[1..close];
+            let (negated, class) = match class.first() {
+                Some(b'!' | b'^') => (true, &class[1..]),
+                _ => (false, class),
+            };
+            let mut hit = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == b'-' {
+                    hit |= class[i] <= c && c <= class[i + 2];
+                    i += 3;
+                } else {
+                    hit |= class[i] == c;
+                    i += 1;
+                }
+            }
+            hit != negated && c != b'/' && glob_match(&pattern[close + 1..], &text[1..])
+        }
+        Some(&p) => text.first() == Some(&p) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+// Depth-first walk sending every file not excluded by the ignore files on the way down
+fn walk(root: &Path, tx: &mpsc::Sender<PathBuf>, cancel: &AtomicBool) {
+    let mut rules = Vec::new();
+    if let Some(repo) = crate::git::Repo::discover(root) {
+        rules.extend(read_ignore_file(&repo.work_tree.join(".git").join("info").join("exclude"), &repo.work_tree));
+        // .gitignore files above the search root still apply below it
+        let mut above: Vec<&Path> = root.ancestors().skip(1).take_while(|dir| dir.starts_with(&repo.work_tree)).collect();
+        above.reverse();
+        for dir in above {
+            rules.extend(read_ignore_file(&dir.join(".gitignore"), dir));
+        }
+    }
+    let mut stack = vec![(root.to_path_buf(), rules)];
+    while let Some((dir, mut rules)) = stack.pop() {
+        if cancel.load(Ordering::Relaxed) { return; }
+        rules.extend(read_ignore_file(&dir.join(".gitignore"), &dir));
+        let Ok(read) = fs::read_dir(&dir) else { continue };
+        let mut entries: Vec<fs::DirEntry> = read.flatten().collect();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries.into_iter().rev() {
+            let path = entry.path();
+            // Symlinks are not followed, so loops can't happen
+            let Ok(kind) = entry.file_type() else { continue };
+            if entry.file_name() == ".git" || is_ignored(&rules, &path, kind.is_dir()) { continue; }
+            if kind.is_dir() {
+                stack.push((path, rules.clone()));
+            } else if kind.is_file() && tx.send(path).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Open the folder search panel for the current file's repository or folder, or close it.
+pub(crate) fn toggle(app: &mut FileViewerApp) {
+    if app.folder_search.take().is_some() { return; }
+    let Some(root) = default_root(app).or_else(|| rfd::FileDialog::new().pick_folder()) else { return };
+    app.folder_search = Some(FolderSearch::new(root, app.search_query.clone()));
+}
+
+fn default_root(app: &FileViewerApp) -> Option<PathBuf> {
+    let path = app.current_path.as_deref()?;
+    match crate::git::Repo::discover(path) {
+        Some(repo) => Some(repo.work_tree),
+        None => path.parent().map(Path::to_path_buf),
+    }
+}
+
+/// Draw the panel; returns the file and match index of a clicked hit.
+pub(crate) fn show(ui: &mut egui::Ui, app: &mut FileViewerApp) -> Option<(PathBuf, usize)> {
+    let ctx = ui.ctx().clone();
+    let mut close = false;
+    let mut opened = None;
+    let search = app.folder_search.as_mut()?;
+    search.receive();
+
+    ui.horizontal(|ui| {
+        ui.heading("Find in Folder");
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            close = ui.button("✕").on_hover_text("Close (Ctrl+Shift+F)").clicked();
+        });
+    });
+    ui.horizontal(|ui| {
+        ui.monospace(search.root.to_string_lossy()).on_hover_text("Folder being searched");
+        if ui.button("Change…").clicked()
+            && let Some(dir) = rfd::FileDialog::new().set_directory(&search.root).pick_folder()
+        {
+            search.root = fs::canonicalize(&dir).unwrap_or(dir);
+            search.start(&ctx);
+        }
+    });
+    ui.horizontal(|ui| {
+        let resp = ui.text_edit_singleline(&mut search.query);
+        if std::mem::take(&mut search.focus_query) {
+            resp.request_focus();
+        }
+        let enter = resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if ui.button("Search").clicked() || enter {
+            search.start(&ctx);
+        }
+    });
+    ui.horizontal(|ui| {
+        let files = search.results.len();
+        if search.is_running() {
+            ui.spinner();
+            ui.label(format!("{} files searched, {} matching lines", search.scanned(), search.total_hits));
+        } else if !search.searched.is_empty() {
+            ui.label(format!("{} matching lines in {} files ({} searched)", search.total_hits, files, search.scanned()));
+        }
+        if search.truncated {
+            ui.label("(stopped at limit)");
+        }
+    });
+    ui.separator();
+
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let text_color = ui.visuals().text_color();
+    egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        for file in &search.results {
+            let rel = file.path.strip_prefix(&search.root).unwrap_or(&file.path);
+            let header = format!("{} ({})", rel.display(), file.hits.len());
+            egui::CollapsingHeader::new(header)
+                .id_salt(&file.path)
+                .default_open(true)
+                .show(ui, |ui| {
+                    for hit in &file.hits {
+                        let mut job = LayoutJob::default();
+                        job.append(&format!("{:>5} ", hit.line + 1), 0.0, egui::TextFormat { font_id: font_id.clone(), color: egui::Color32::GRAY, ..Default::default() });
+                        let mut counter = 0;
+                        crate::highlight::append_with_search(&mut job, &hit.preview, font_id.clone(), text_color, &search.searched, usize::MAX, &mut counter);
+                        if ui.add(egui::Label::new(job).truncate().sense(egui::Sense::click())).clicked() {
+                            opened = Some((file.path.clone(), hit.match_index));
+                        }
+                    }
+                });
+        }
+    });
+
+    if close {
+        app.folder_search = None;
+    }
+    opened
+}
+
+/// Open a clicked hit with its query in the find bar and the match selected.
+pub(crate) fn open_hit(app: &mut FileViewerApp, ctx: &egui::Context, path: PathBuf, match_index: usize) {
+    let Some(query) = app.folder_search.as_ref().map(|s| s.searched.clone()) else { return };
+    app.load_file(path.clone(), ctx);
+    if app.current_path.as_deref() != Some(path.as_path()) { return; }
+    app.show_search_bar = true;
+    app.search_count = match &app.content {
+        Some(crate::app::Content::Text(text)) => crate::search::recompute_count(&query, text),
+        _ => 0,
+    };
+    app.search_query = query;
+    app.search_current = match_index.min(app.search_count.saturating_sub(1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        glob_match(pattern.as_bytes(), text.as_bytes())
+    }
+
+    #[test]
+    fn star_stays_within_a_component() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(matches("*", ""));
+        assert!(!matches("*.rs", "src/main.rs"));
+        assert!(matches("src/*.rs", "src/main.rs"));
+        assert!(!matches("src/*.rs", "src/bin/main.rs"));
+        assert!(matches("?.txt", "a.txt"));
+        assert!(!matches("?", "/"));
+    }
+
+    #[test]
+    fn double_star_crosses_components() {
+        assert!(matches("**/foo", "foo"));
+        assert!(matches("**/foo", "a/b/foo"));
+        assert!(matches("a/**/b", "a/b"));
+        assert!(matches("a/**/b", "a/x/y/b"));
+        assert!(matches("a/**", "a/x/y"));
+        assert!(!matches("a/**/b", "x/a/b"));
+    }
+
+    #[test]
+    fn character_classes() {
+        assert!(matches("[abc].txt", "b.txt"));
+        assert!(!matches("[abc].txt", "d.txt"));
+        assert!(matches("file[0-9]", "file7"));
+        assert!(!matches("file[0-9]", "filex"));
+        assert!(matches("[!a]x", "bx"));
+        assert!(!matches("[!a]x", "ax"));
+        assert!(matches("[^a]x", "bx"));
+        assert!(!matches("a[!b]c", "a/c"));
+        assert!(matches("[]]", "]"));
+        // An unclosed bracket is a literal
+        assert!(matches("[ab", "[ab"));
+    }
+
+    #[test]
+    fn dir_only_rules_skip_files() {
+        let base = PathBuf::from("/repo");
+        let rule = |pattern: &str, negate, dir_only, anchored| IgnoreRule { base: base.clone(), pattern: pattern.to_string(), negate, dir_only, anchored };
+        let rules = [rule("build", false, true, false)];
+        assert!(is_ignored(&rules, Path::new("/repo/build"), true));
+        assert!(is_ignored(&rules, Path::new("/repo/sub/build"), true));
+        assert!(!is_ignored(&rules, Path::new("/repo/build"), false));
+        let rules = [rule("*.log", false, false, false), rule("keep.log", true, false, false)];
+        assert!(is_ignored(&rules, Path::new("/repo/a/x.log"), false));
+        assert!(!is_ignored(&rules, Path::new("/repo/keep.log"), false));
+        let rules = [rule("docs/*.md", false, false, true)];
+        assert!(is_ignored(&rules, Path::new("/repo/docs/a.md"), false));
+        assert!(!is_ignored(&rules, Path::new("/repo/src/docs/a.md"), false));
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeSet;
+
+/// Collapsible regions of a text file. A region starting at line `s` hides
+/// lines `s + 1 .. end`; the first line stays visible with a placeholder.
+pub(crate) struct FoldRegions {
+    ends: Vec<Option<usize>>,
+}
+
+impl FoldRegions {
+    /// Regions from bracket pairs spanning lines (skipping strings and comments)
+    /// plus indentation blocks, so brace languages, JSON, Python, YAML and
+    /// plain outlines all fold.
+    pub(crate) fn compute(text: &str, ext: &str) -> Self {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut ends = vec![None; lines.len()];
+        for (start, end) in indent_regions(&lines) {
+            ends[start] = Some(end);
+        }
+        // Bracket pairs win where both start on the same line; of several pairs
+        // opened on one line, the innermost (closed first) is kept
+        let mut from_bracket = vec![false; lines.len()];
+        for (start, end) in bracket_regions(&lines, ext) {
+            if !std::mem::replace(&mut from_bracket[start], true) {
+                ends[start] = Some(end);
+            }
+        }
+        Self { ends }
+    }
+
+    /// End (exclusive) of the lines hidden when `line` is folded.
+    pub(crate) fn end(&self, line: usize) -> Option<usize> {
+        self.ends.get(line).copied().flatten()
+    }
+
+    pub(crate) fn starts(&self) -> impl Iterator<Item = usize> + '_ {
+        self.ends.iter().enumerate().filter_map(|(i, end)| end.map(|_| i))
+    }
+
+    /// The folded region hiding `line`, if any (outermost first).
+    pub(crate) fn folded_around(&self, folded: &BTreeSet<usize>, line: usize) -> Option<usize> {
+        folded.iter().copied().find(|&s| s < line && self.end(s).is_some_and(|e| line < e))
+    }
+}
+
+fn indent_of(line: &str) -> Option<usize> {
+    if line.trim().is_empty() { return None; }
+    Some(line.chars().take_while(|c| c.is_whitespace()).map(|c| if c == '\t' { 4 } else { 1 }).sum())
+}
+
+// A line followed by more-indented lines folds them, up to the last non-blank one
+fn indent_regions(lines: &[&str]) -> Vec<(usize, usize)> {
+    let indents: Vec<Option<usize>> = lines.iter().map(|l| indent_of(l)).collect();
+    let mut regions = Vec::new();
+    // Open blocks as (start line, its indent)
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    let mut last_content = 0;
+    for (i, indent) in indents.iter().enumerate() {
+        let Some(indent) = *indent else { continue };
+        while let Some(&(start, open)) = stack.last() {
+            if indent > open { break; }
+            stack.pop();
+            if last_content > start { regions.push((start, last_content + 1)); }
+        }
+        stack.push((i, indent));
+        last_content = i;
+    }
+    for (start, _) in stack {
+        if last_content > start { regions.push((start, last_content + 1)); }
+    }
+    regions
+}
+
+// Pairs of (), [] and {} opening and closing on different lines. A closer that
+// starts its line stays visible; otherwise the closing line folds too.
+fn bracket_regions(lines: &[&str], ext: &str) -> Vec<(usize, usize)> {
+    let mut regions = Vec::new();
+    let mut stack: Vec<(u8, usize)> = Vec::new();
+    for (i, j, b) in crate::brackets::scan(lines, ext) {
+        let Some(open) = crate::brackets::opener_of(b) else {
+            stack.push((b, i));
+            continue;
+        };
+        // Unbalanced closers are ignored rather than unwinding the stack
+        if stack.last().is_some_and(|(c, _)| *c == open)
+            && let Some((_, start)) = stack.pop()
+        {
+            let first_char = lines[i].len() - lines[i].trim_start().len();
+            let end = if j == first_char { i } else { i + 1 };
+            if end > start + 1 { regions.push((start, end)); }
+        }
+    }
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ends(text: &str, ext: &str) -> Vec<Option<usize>> {
+        FoldRegions::compute(text, ext).ends
+    }
+
+    #[test]
+    fn closer_on_its_own_line_stays_visible() {
+        let text = "fn main() {\n    a();\n    b();\n}\n";
+        assert_eq!(ends(text, "rs"), vec![Some(3), None, None, None]);
+        let text = "if x {\n    a();\n} else {\n    b();\n}\n";
+        assert_eq!(ends(text, "rs"), vec![Some(2), None, Some(4), None, None]);
+    }
+
+    #[test]
+    fn closer_after_code_folds_its_line() {
+        let text = "if x {\n    a();\n    b(); }\nnext();\n";
+        assert_eq!(ends(text, "rs")[0], Some(3));
+    }
+
+    #[test]
+    fn indent_blocks_leave_trailing_blank_lines_out() {
+        let text = "def f():\n    a = 1\n    b = 2\n\n\nx = 3\n";
+        assert_eq!(ends(text, "py"), vec![Some(3), None, None, None, None, None]);
+    }
+
+    #[test]
+    fn innermost_of_pairs_opened_on_one_line_wins() {
+        let text = "call(vec![\n    1,\n    2,\n]);\n";
+        assert_eq!(ends(text, "rs")[0], Some(3));
+    }
+
+    #[test]
+    fn brackets_in_strings_and_comments_do_not_fold() {
+        let text = "let s = \"{\";\n    // (\n    x\n";
+        assert_eq!(ends(text, "rs")[0], Some(3));
+        assert!(bracket_regions(&text.lines().collect::<Vec<_>>(), "rs").is_empty());
+    }
+
+    #[test]
+    fn folded_around_finds_the_outermost_fold() {
+        let regions = FoldRegions::compute("a {\n  b {\n    c\n  }\n}\n", "rs");
+        let folded = BTreeSet::from([0, 1]);
+        assert_eq!(regions.folded_around(&folded, 2), Some(0));
+        assert_eq!(regions.folded_around(&folded, 4), None);
+        assert_eq!(regions.starts().collect::<Vec<_>>(), vec![0, 1]);
+    }
+}
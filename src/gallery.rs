@@ -0,0 +1,326 @@
+use eframe::egui;
+use egui::{Color32, Rect, TextureHandle};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::SystemTime;
+use crate::app::FileViewerApp;
+
+// Longest side of a generated thumbnail, in pixels
+const THUMB_SIZE: u32 = 160;
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+const CELL_PADDING: f32 = 6.0;
+const LABEL_HEIGHT: f32 = 18.0;
+const MAX_LABEL_CHARS: usize = 22;
+
+/// Order of the gallery grid.
+#[derive(Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum GallerySort {
+    Name,
+    /// Newest first
+    Date,
+    /// Largest first
+    Size,
+}
+
+impl GallerySort {
+    pub(crate) const ALL: [GallerySort; 3] = [GallerySort::Name, GallerySort::Date, GallerySort::Size];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            GallerySort::Name => "Name",
+            GallerySort::Date => "Date",
+            GallerySort::Size => "Size",
+        }
+    }
+}
+
+struct GalleryEntry {
+    path: PathBuf,
+    name: String,
+    modified: SystemTime,
+    size: u64,
+}
+
+enum Thumb {
+    Ready(TextureHandle),
+    Failed,
+}
+
+/// Thumbnail grid of the images in one directory. Thumbnails are produced on a
+/// background thread and arrive over a channel; dropping the gallery stops it.
+pub(crate) struct Gallery {
+    pub(crate) dir: PathBuf,
+    entries: Vec<GalleryEntry>,
+    selected: usize,
+    // Bring the selection into view on the next frame (after opening or a key press)
+    scroll_to_selected: bool,
+    scroll_y: f32,
+    view_height: f32,
+    thumbs: HashMap<PathBuf, Thumb>,
+    rx: mpsc::Receiver<(PathBuf, Option<image::RgbaImage>)>,
+}
+
+impl Gallery {
+    pub(crate) fn open(ctx: &egui::Context, dir: PathBuf, sort: GallerySort, current: Option<&Path>) -> Self {
+        let mut entries: Vec<GalleryEntry> = crate::io::list_images(&dir)
+            .into_iter()
+            .map(|path| {
+                let md = fs::metadata(&path).ok();
+                GalleryEntry {
+                    name: path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+                    modified: md.as_ref().and_then(|m| m.modified().ok()).unwrap_or(SystemTime::UNIX_EPOCH),
+                    size: md.map_or(0, |m| m.len()),
+                    path,
+                }
+            })
+            .collect();
+        sort_entries(&mut entries, sort);
+        let selected = current
+            .and_then(|cur| entries.iter().position(|e| e.path == cur))
+            .unwrap_or(0);
+        let paths = entries.iter().map(|e| e.path.clone()).collect();
+        Self {
+            dir,
+            entries,
+            selected,
+            scroll_to_selected: true,
+            scroll_y: 0.0,
+            view_height: 0.0,
+            thumbs: HashMap::new(),
+            rx: spawn_worker(ctx.clone(), paths),
+        }
+    }
+
+    pub(crate) fn resort(&mut self, sort: GallerySort) {
+        let selected = self.entries.get(self.selected).map(|e| e.path.clone());
+        sort_entries(&mut self.entries, sort);
+        self.selected = selected
+            .and_then(|p| self.entries.iter().position(|e| e.path == p))
+            .unwrap_or(0);
+        self.scroll_to_selected = true;
+    }
+
+    fn receive(&mut self, ctx: &egui::Context) {
+        while let Ok((path, thumb)) = self.rx.try_recv() {
+            let thumb = match thumb {
+                Some(rgba) => {
+                    let size = [rgba.width() as usize, rgba.height() as usize];
+                    let pixels = egui::ColorImage::from_rgba_unmultiplied(size, rgba.as_raw());
+                    Thumb::Ready(ctx.load_texture(path.to_string_lossy(), pixels, egui::TextureOptions::LINEAR))
+                }
+                None => Thumb::Failed,
+            };
+            self.thumbs.insert(path, thumb);
+        }
+    }
+}
+
+fn sort_entries(entries: &mut [GalleryEntry], sort: GallerySort) {
+    match sort {
+        GallerySort::Name => entries.sort_by(|a, b| crate::listing::natural_cmp(&a.name, &b.name)),
+        GallerySort::Date => entries.sort_by_key(|e| std::cmp::Reverse(e.modified)),
+        GallerySort::Size => entries.sort_by_key(|e| std::cmp::Reverse(e.size)),
+    }
+}
+
+/// Open the gallery for the current file's folder (or a picked folder), or close it.
+pub(crate) fn toggle(app: &mut FileViewerApp, ctx: &egui::Context) {
+    if app.gallery.take().is_some() { return; }
+    let dir = match app.current_path.as_deref().and_then(Path::parent) {
+        Some(dir) => dir.to_path_buf(),
+        None => match rfd::FileDialog::new().pick_folder() {
+            Some(dir) => dir,
+            None => return,
+        },
+    };
+    app.gallery = Some(Gallery::open(ctx, dir, app.gallery_sort, app.current_path.as_deref()));
+}
+
+// Generate thumbnails in grid order; stops once the gallery (the receiver) is dropped
+fn spawn_worker(ctx: egui::Context, paths: Vec<PathBuf>) -> mpsc::Receiver<(PathBuf, Option<image::RgbaImage>)> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for path in paths {
+            let thumb = cached_thumbnail(&path);
+            if tx.send((path, thumb)).is_err() { return; }
+            ctx.request_repaint();
+        }
+    });
+    rx
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "gemini-file-viewer")
+        .map(|dirs| dirs.cache_dir().join("thumbnails"))
+}
+
+// Keyed by path, size and modification time so edited files get a fresh thumbnail.
+// FNV-1a rather than std's hasher, whose output may change between Rust releases
+// and would orphan the whole cache.
+fn cache_file(path: &Path) -> Option<PathBuf> {
+    let md = fs::metadata(path).ok()?;
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let modified = md.modified().ok().and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok()).map_or(0, |d| d.as_nanos());
+    let mut hash = FNV_OFFSET;
+    for bytes in [path.as_os_str().as_encoded_bytes(), &md.len().to_le_bytes(), &modified.to_le_bytes(), &THUMB_SIZE.to_le_bytes()] {
+        for &b in bytes {
+            hash = (hash ^ u64::from(b)).wrapping_mul(FNV_PRIME);
+        }
+    }
+    Some(cache_dir()?.join(format!("{:016x}.png", hash)))
+}
+
+fn cached_thumbnail(path: &Path) -> Option<image::RgbaImage> {
+    let cached = cache_file(path);
+    if let Some(file) = &cached
+        && let Ok(img) = image::open(file)
+    {
+        return Some(img.to_rgba8());
+    }
+    let thumb = crate::io::load_thumbnail(path, THUMB_SIZE).ok()?;
+    if let Some(file) = cached {
+        if let Some(parent) = file.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let _ = thumb.save(file);
+    }
+    Some(thumb)
+}
+
+fn short_name(name: &str) -> String {
+    if name.chars().count() <= MAX_LABEL_CHARS {
+        name.to_string()
+    } else {
+        name.chars().take(MAX_LABEL_CHARS - 1).collect::<String>() + "…"
+    }
+}
+
+/// Draw the gallery; returns the image to open when one is activated.
+pub(crate) fn show(ui: &mut egui::Ui, app: &mut FileViewerApp) -> Option<PathBuf> {
+    let ctx = ui.ctx().clone();
+    let mut sort = app.gallery_sort;
+    let mut close = false;
+    let gallery = app.gallery.as_mut()?;
+    gallery.receive(&ctx);
+
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new(gallery.dir.to_string_lossy()).strong());
+        ui.label(format!("{} images", gallery.entries.len()));
+        let pending = gallery.entries.len().saturating_sub(gallery.thumbs.len());
+        if pending > 0 {
+            ui.spinner();
+            ui.label(format!("{} thumbnails left", pending));
+        }
+        ui.separator();
+        egui::ComboBox::from_label("Sort")
+            .selected_text(sort.label())
+            .show_ui(ui, |ui| {
+                for option in GallerySort::ALL {
+                    ui.selectable_value(&mut sort, option, option.label());
+                }
+            });
+        if ui.button("Close").on_hover_text("Back to the viewer (Esc)").clicked() {
+            close = true;
+        }
+    });
+    ui.separator();
+    if sort != app.gallery_sort {
+        app.gallery_sort = sort;
+        gallery.resort(sort);
+    }
+    if gallery.entries.is_empty() {
+        ui.label("No images in this folder.");
+    }
+
+    let spacing = ui.spacing().item_spacing;
+    let thumb = THUMB_SIZE as f32;
+    let cell = egui::vec2(thumb + CELL_PADDING * 2.0, thumb + CELL_PADDING * 2.0 + LABEL_HEIGHT);
+    let columns = (((ui.available_width() + spacing.x) / (cell.x + spacing.x)).floor() as usize).max(1);
+    let rows = gallery.entries.len().div_ceil(columns);
+
+    // Keyboard navigation: arrows move the selection, Enter opens, Esc closes
+    let mut open = None;
+    if !ctx.wants_keyboard_input() && !gallery.entries.is_empty() {
+        let last = gallery.entries.len() - 1;
+        let before = gallery.selected;
+        ui.input(|i| {
+            let sel = &mut gallery.selected;
+            if i.key_pressed(egui::Key::ArrowRight) { *sel = (*sel + 1).min(last); }
+            if i.key_pressed(egui::Key::ArrowLeft) { *sel = sel.saturating_sub(1); }
+            if i.key_pressed(egui::Key::ArrowDown) { *sel = (*sel + columns).min(last); }
+            if i.key_pressed(egui::Key::ArrowUp) { *sel = sel.saturating_sub(columns); }
+            if i.key_pressed(egui::Key::Home) { *sel = 0; }
+            if i.key_pressed(egui::Key::End) { *sel = last; }
+            if i.key_pressed(egui::Key::Enter) { open = Some(*sel); }
+            if i.key_pressed(egui::Key::Escape) { close = true; }
+        });
+        if gallery.selected != before {
+            gallery.scroll_to_selected = true;
+        }
+    }
+
+    let mut area = egui::ScrollArea::vertical().auto_shrink([false, false]);
+    if std::mem::take(&mut gallery.scroll_to_selected) && gallery.view_height > 0.0 {
+        let row_height = cell.y + spacing.y;
+        let top = (gallery.selected / columns) as f32 * row_height;
+        let mut offset = gallery.scroll_y;
+        if top < offset {
+            offset = top;
+        } else if top + cell.y > offset + gallery.view_height {
+            offset = top + cell.y - gallery.view_height;
+        }
+        area = area.vertical_scroll_offset(offset);
+    }
+    let output = area.show_rows(ui, cell.y, rows, |ui, row_range| {
+        for row in row_range {
+            ui.horizontal(|ui| {
+                for index in row * columns..((row + 1) * columns).min(gallery.entries.len()) {
+                    let entry = &gallery.entries[index];
+                    let (rect, resp) = ui.allocate_exact_size(cell, egui::Sense::click());
+                    let visuals = ui.visuals();
+                    if index == gallery.selected {
+                        ui.painter().rect_filled(rect, 4.0, visuals.selection.bg_fill);
+                    } else if resp.hovered() {
+                        ui.painter().rect_filled(rect, 4.0, visuals.widgets.hovered.weak_bg_fill);
+                    }
+                    let slot = Rect::from_min_size(rect.min + egui::vec2(CELL_PADDING, CELL_PADDING), egui::vec2(thumb, thumb));
+                    match gallery.thumbs.get(&entry.path) {
+                        Some(Thumb::Ready(texture)) => {
+                            let size = texture.size_vec2();
+                            let scale = (thumb / size.x.max(size.y)).min(1.0);
+                            let image_rect = Rect::from_center_size(slot.center(), size * scale);
+                            let uv = Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                            ui.painter().image(texture.id(), image_rect, uv, Color32::WHITE);
+                        }
+                        Some(Thumb::Failed) => {
+                            ui.painter().text(slot.center(), egui::Align2::CENTER_CENTER, "?", egui::FontId::proportional(32.0), visuals.weak_text_color());
+                        }
+                        None => {
+                            ui.painter().text(slot.center(), egui::Align2::CENTER_CENTER, "…", egui::FontId::proportional(24.0), visuals.weak_text_color());
+                        }
+                    }
+                    let label_pos = egui::pos2(rect.center().x, rect.max.y - CELL_PADDING - LABEL_HEIGHT / 2.0);
+                    ui.painter().text(label_pos, egui::Align2::CENTER_CENTER, short_name(&entry.name), egui::FontId::proportional(12.0), visuals.text_color());
+                    let resp = resp.on_hover_text(&entry.name);
+                    if resp.clicked() {
+                        gallery.selected = index;
+                    }
+                    if resp.double_clicked() {
+                        open = Some(index);
+                    }
+                }
+            });
+        }
+    });
+    gallery.scroll_y = output.state.offset.y;
+    gallery.view_height = output.inner_rect.height();
+
+    let path = open.and_then(|index| gallery.entries.get(index)).map(|e| e.path.clone());
+    if close || path.is_some() {
+        app.gallery = None;
+    }
+    path
+}
@@ -0,0 +1,598 @@
+use flate2::read::ZlibDecoder;
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+// git's own cap on delta chains (pack.depth is clamped to 4095); deeper ones are treated as corrupt
+const MAX_DELTA_DEPTH: usize = 4095;
+// Larger objects are skipped rather than inflated into memory
+const MAX_OBJECT_BYTES: usize = 64 * 1024 * 1024;
+
+pub(crate) type ObjectId = [u8; 20];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ObjectKind {
+    Commit,
+    Tree,
+    Blob,
+    Tag,
+}
+
+/// How a line of the working file differs from HEAD.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LineChange {
+    Added,
+    Modified,
+    /// Lines were removed just above this one
+    Deleted,
+}
+
+/// Gutter glyph and color for a line, a blank of the same width when unchanged.
+pub(crate) fn gutter_marker(change: Option<LineChange>) -> (&'static str, eframe::egui::Color32) {
+    use eframe::egui::Color32;
+    match change {
+        Some(LineChange::Added) => ("▌", Color32::from_rgb(80, 180, 90)),
+        Some(LineChange::Modified) => ("▌", Color32::from_rgb(90, 150, 230)),
+        Some(LineChange::Deleted) => ("▔", Color32::from_rgb(220, 80, 80)),
+        None => (" ", Color32::TRANSPARENT),
+    }
+}
+
+/// Read-only access to a local repository: refs, loose objects and packs.
+/// SHA-1 repositories only; anything else is treated as "not a repo".
+pub(crate) struct Repo {
+    // Per-worktree state such as HEAD
+    git_dir: PathBuf,
+    // Objects and refs, shared between worktrees
+    common_dir: PathBuf,
+    pub(crate) work_tree: PathBuf,
+    packs: Vec<PathBuf>,
+}
+
+pub(crate) struct Commit {
+    pub(crate) tree: ObjectId,
+    pub(crate) parents: Vec<ObjectId>,
+    pub(crate) author: String,
+    /// Author time, in seconds since the Unix epoch
+    pub(crate) time: i64,
+    pub(crate) message: String,
+}
+
+pub(crate) struct Head {
+    /// Branch name, or None when HEAD is detached
+    pub(crate) branch: Option<String>,
+    /// None on a branch without commits yet
+    pub(crate) commit: Option<ObjectId>,
+}
+
+impl Repo {
+    /// Find the repository whose working tree contains `path`.
+    pub(crate) fn discover(path: &Path) -> Option<Repo> {
+        let path = fs::canonicalize(path).ok()?;
+        for dir in path.ancestors() {
+            let dot_git = dir.join(".git");
+            let git_dir = if dot_git.is_dir() {
+                dot_git
+            } else if dot_git.is_file() {
+                // Worktrees and submodules point at their git dir from a `.git` file
+                let link = fs::read_to_string(&dot_git).ok()?;
+                dir.join(link.strip_prefix("gitdir:")?.trim())
+            } else {
+                continue;
+            };
+            if !git_dir.join("HEAD").is_file() { return None; }
+            let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+                Ok(rel) => git_dir.join(rel.trim()),
+                Err(_) => git_dir.clone(),
+            };
+            if uses_sha256(&common_dir) { return None; }
+            let packs = fs::read_dir(common_dir.join("objects").join("pack"))
+                .map(|read| {
+                    read.flatten()
+                        .map(|e| e.path())
+                        .filter(|p| p.extension().is_some_and(|e| e == "pack"))
+                        .collect()
+                })
+                .unwrap_or_default();
+            return Some(Repo { git_dir, common_dir, work_tree: dir.to_path_buf(), packs });
+        }
+        None
+    }
+
+    /// `path` relative to the working tree, as used in tree objects.
+    pub(crate) fn relative_path(&self, path: &Path) -> Option<PathBuf> {
+        let path = fs::canonicalize(path).ok()?;
+        path.strip_prefix(&self.work_tree).ok().map(Path::to_path_buf)
+    }
+
+    pub(crate) fn head(&self) -> Option<Head> {
+        let head = fs::read_to_string(self.git_dir.join("HEAD")).ok()?;
+        match head.trim().strip_prefix("ref:") {
+            Some(name) => {
+                let name = name.trim();
+                let branch = name.strip_prefix("refs/heads/").unwrap_or(name).to_string();
+                Some(Head { branch: Some(branch), commit: self.resolve_ref(name) })
+            }
+            None => Some(Head { branch: None, commit: Some(parse_hex(head.trim())?) }),
+        }
+    }
+
+    fn resolve_ref(&self, name: &str) -> Option<ObjectId> {
+        let mut name = name.to_string();
+        // Follow symbolic refs a few levels deep
+        for _ in 0..8 {
+            let loose = [&self.git_dir, &self.common_dir]
+                .iter()
+                .find_map(|dir| fs::read_to_string(dir.join(&name)).ok());
+            match loose {
+                Some(value) => match value.trim().strip_prefix("ref:") {
+                    Some(target) => name = target.trim().to_string(),
+                    None => return parse_hex(value.trim()),
+                },
+                None => return self.packed_ref(&name),
+            }
+        }
+        None
+    }
+
+    fn packed_ref(&self, name: &str) -> Option<ObjectId> {
+        let packed = fs::read_to_string(self.common_dir.join("packed-refs")).ok()?;
+        packed
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+            .find_map(|line| {
+                let (id, ref_name) = line.split_once(' ')?;
+                (ref_name.trim() == name).then(|| parse_hex(id)).flatten()
+            })
+    }
+
+    pub(crate) fn read_object(&self, id: &ObjectId) -> Option<(ObjectKind, Vec<u8>)> {
+        // Follow the delta chain down to a full object, then apply the deltas back up;
+        // a loop rather than recursion, since chains can be thousands deep
+        let mut deltas = Vec::new();
+        let mut entry = self.find_entry(id)?;
+        let (kind, mut data) = loop {
+            match entry {
+                PackEntry::Object(kind, data) => break (kind, data),
+                PackEntry::Delta(delta, base) => {
+                    if deltas.len() == MAX_DELTA_DEPTH { return None; }
+                    deltas.push(delta);
+                    entry = match base {
+                        DeltaBase::Id(id) => self.find_entry(&id)?,
+                        DeltaBase::Offset(pack, offset) => read_packed(pack, offset)?,
+                    };
+                }
+            }
+        };
+        for delta in deltas.iter().rev() {
+            data = apply_delta(&data, delta)?;
+        }
+        Some((kind, data))
+    }
+
+    fn find_entry(&self, id: &ObjectId) -> Option<PackEntry<'_>> {
+        let hex = to_hex(id);
+        let loose = self.common_dir.join("objects").join(&hex[..2]).join(&hex[2..]);
+        if let Ok(file) = File::open(&loose) {
+            let (kind, data) = read_loose(file)?;
+            return Some(PackEntry::Object(kind, data));
+        }
+        self.packs.iter().find_map(|pack| {
+            let offset = find_in_index(&pack.with_extension("idx"), id)?;
+            read_packed(pack, offset)
+        })
+    }
+
+    /// Parsed commit object.
+    pub(crate) fn commit(&self, id: &ObjectId) -> Option<Commit> {
+        let (kind, data) = self.read_object(id)?;
+        if kind != ObjectKind::Commit { return None; }
+        let text = String::from_utf8_lossy(&data);
+        let (headers, message) = text.split_once("\n\n").unwrap_or((&text, ""));
+        let mut commit = Commit {
+            tree: [0; 20],
+            parents: Vec::new(),
+            author: String::new(),
+            time: 0,
+            message: message.trim_end().to_string(),
+        };
+        let mut has_tree = false;
+        for line in headers.lines() {
+            if let Some(tree) = line.strip_prefix("tree ") {
+                commit.tree = parse_hex(tree)?;
+                has_tree = true;
+            } else if let Some(parent) = line.strip_prefix("parent ") {
+                commit.parents.push(parse_hex(parent)?);
+            } else if let Some(author) = line.strip_prefix("author ") {
+                // "Name <email> <unix time> <tz>"
+                let (name, rest) = author.rsplit_once('<').unwrap_or((author, ""));
+                commit.author = name.trim().to_string();
+                commit.time = rest.split_whitespace().rev().nth(1).and_then(|t| t.parse().ok()).unwrap_or(0);
+            }
+        }
+        has_tree.then_some(commit)
+    }
+
+    /// Id of the blob at `rel` (relative to the working tree) in `commit`.
+    pub(crate) fn blob_id_at(&self, commit: &ObjectId, rel: &Path) -> Option<ObjectId> {
+        let mut id = self.commit(commit)?.tree;
+        for component in rel.components() {
+            let (kind, data) = self.read_object(&id)?;
+            if kind != ObjectKind::Tree { return None; }
+            id = tree_entry(&data, component.as_os_str().to_str()?)?;
+        }
+        Some(id)
+    }
+
+    /// Contents of the file at `rel` (relative to the working tree) in `commit`.
+    pub(crate) fn blob_at(&self, commit: &ObjectId, rel: &Path) -> Option<Vec<u8>> {
+        self.blob(&self.blob_id_at(commit, rel)?)
+    }
+
+    pub(crate) fn blob(&self, id: &ObjectId) -> Option<Vec<u8>> {
+        match self.read_object(id)? {
+            (ObjectKind::Blob, data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+fn uses_sha256(common_dir: &Path) -> bool {
+    fs::read_to_string(common_dir.join("config")).is_ok_and(|config| {
+        config.lines().any(|line| {
+            line.split_once('=')
+                .is_some_and(|(key, value)| key.trim().eq_ignore_ascii_case("objectformat") && value.trim() != "sha1")
+        })
+    })
+}
+
+pub(crate) fn parse_hex(s: &str) -> Option<ObjectId> {
+    if s.len() != 40 { return None; }
+    let mut id = [0u8; 20];
+    for (i, byte) in id.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(s.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(id)
+}
+
+pub(crate) fn to_hex(id: &ObjectId) -> String {
+    id.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn kind_from_name(name: &[u8]) -> Option<ObjectKind> {
+    match name {
+        b"commit" => Some(ObjectKind::Commit),
+        b"tree" => Some(ObjectKind::Tree),
+        b"blob" => Some(ObjectKind::Blob),
+        b"tag" => Some(ObjectKind::Tag),
+        _ => None,
+    }
+}
+
+// Loose objects are zlib-compressed "<kind> <size>\0<data>"
+fn read_loose(file: File) -> Option<(ObjectKind, Vec<u8>)> {
+    let mut data = Vec::new();
+    ZlibDecoder::new(BufReader::new(file))
+        .take(MAX_OBJECT_BYTES as u64 + 64)
+        .read_to_end(&mut data)
+        .ok()?;
+    let nul = data.iter().position(|b| *b == 0)?;
+    let (kind, size) = std::str::from_utf8(&data[..nul]).ok()?.split_once(' ')?;
+    let kind = kind_from_name(kind.as_bytes())?;
+    let size: usize = size.parse().ok()?;
+    let body = data.split_off(nul + 1);
+    (body.len() == size).then_some((kind, body))
+}
+
+// Offset of `id` in the pack, from a version 2 pack index
+fn find_in_index(idx: &Path, id: &ObjectId) -> Option<u64> {
+    let mut file = File::open(idx).ok()?;
+    let header: [u8; 8] = read_at(&mut file, 0)?;
+    if header != [0xff, b't', b'O', b'c', 0, 0, 0, 2] { return None; }
+    let mut fanout = |i: u64| read_at(&mut file, 8 + i * 4).map(|w| u32::from_be_bytes(w) as u64);
+    let first = id[0] as u64;
+    let mut lo = if first == 0 { 0 } else { fanout(first - 1)? };
+    let mut hi = fanout(first)?;
+    let total = fanout(255)?;
+    let names = 8 + 256 * 4;
+    let offsets = names + total * 20 + total * 4;
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        let name: ObjectId = read_at(&mut file, names + mid * 20)?;
+        match name.cmp(id) {
+            std::cmp::Ordering::Less => lo = mid + 1,
+            std::cmp::Ordering::Greater => hi = mid,
+            std::cmp::Ordering::Equal => {
+                let offset = u32::from_be_bytes(read_at(&mut file, offsets + mid * 4)?);
+                if offset & 0x8000_0000 == 0 { return Some(offset as u64); }
+                // Packs over 2GB keep large offsets in a separate table
+                let large = read_at(&mut file, offsets + total * 4 + (offset & 0x7fff_ffff) as u64 * 8)?;
+                return Some(u64::from_be_bytes(large));
+            }
+        }
+    }
+    None
+}
+
+fn read_at<const N: usize>(file: &mut File, pos: u64) -> Option<[u8; N]> {
+    let mut buf = [0u8; N];
+    file.seek(SeekFrom::Start(pos)).ok()?;
+    file.read_exact(&mut buf).ok()?;
+    Some(buf)
+}
+
+fn read_byte(reader: &mut impl Read) -> Option<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte).ok()?;
+    Some(byte[0])
+}
+
+fn inflate(reader: &mut impl Read, size: u64) -> Option<Vec<u8>> {
+    if size > MAX_OBJECT_BYTES as u64 { return None; }
+    let mut data = Vec::with_capacity(size as usize);
+    ZlibDecoder::new(reader).take(size).read_to_end(&mut data).ok()?;
+    (data.len() as u64 == size).then_some(data)
+}
+
+// A pack entry: a full object, or a delta against another object
+enum PackEntry<'a> {
+    Object(ObjectKind, Vec<u8>),
+    Delta(Vec<u8>, DeltaBase<'a>),
+}
+
+enum DeltaBase<'a> {
+    Id(ObjectId),
+    // Earlier entry of the same pack
+    Offset(&'a Path, u64),
+}
+
+fn read_packed(pack: &Path, offset: u64) -> Option<PackEntry<'_>> {
+    let mut reader = BufReader::new(File::open(pack).ok()?);
+    reader.seek(SeekFrom::Start(offset)).ok()?;
+    // Type in bits 4-6 of the first byte, size as a little-endian varint
+    let mut byte = read_byte(&mut reader)?;
+    let kind = (byte >> 4) & 7;
+    let mut size = (byte & 0x0f) as u64;
+    let mut shift = 4;
+    while byte & 0x80 != 0 {
+        if shift > 57 { return None; }
+        byte = read_byte(&mut reader)?;
+        size |= ((byte & 0x7f) as u64) << shift;
+        shift += 7;
+    }
+    match kind {
+        1 => Some(PackEntry::Object(ObjectKind::Commit, inflate(&mut reader, size)?)),
+        2 => Some(PackEntry::Object(ObjectKind::Tree, inflate(&mut reader, size)?)),
+        3 => Some(PackEntry::Object(ObjectKind::Blob, inflate(&mut reader, size)?)),
+        4 => Some(PackEntry::Object(ObjectKind::Tag, inflate(&mut reader, size)?)),
+        // Delta against an earlier object in the same pack
+        6 => {
+            let mut byte = read_byte(&mut reader)?;
+            let mut back = (byte & 0x7f) as u64;
+            while byte & 0x80 != 0 {
+                byte = read_byte(&mut reader)?;
+                back = back.checked_add(1)?.checked_mul(128)? | (byte & 0x7f) as u64;
+            }
+            let delta = inflate(&mut reader, size)?;
+            Some(PackEntry::Delta(delta, DeltaBase::Offset(pack, offset.checked_sub(back)?)))
+        }
+        // Delta against an object named by id
+        7 => {
+            let mut base_id = [0u8; 20];
+            reader.read_exact(&mut base_id).ok()?;
+            let delta = inflate(&mut reader, size)?;
+            Some(PackEntry::Delta(delta, DeltaBase::Id(base_id)))
+        }
+        _ => None,
+    }
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Option<usize> {
+    let (mut value, mut shift) = (0usize, 0);
+    loop {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 { return Some(value); }
+        shift += 7;
+        if shift > 56 { return None; }
+    }
+}
+
+// A delta is the base and result sizes followed by copy-from-base and insert instructions
+fn apply_delta(base: &[u8], delta: &[u8]) -> Option<Vec<u8>> {
+    let mut pos = 0;
+    if read_varint(delta, &mut pos)? != base.len() { return None; }
+    let result_len = read_varint(delta, &mut pos)?;
+    if result_len > MAX_OBJECT_BYTES { return None; }
+    let mut out = Vec::with_capacity(result_len);
+    while pos < delta.len() {
+        let op = delta[pos];
+        pos += 1;
+        if op & 0x80 != 0 {
+            let (mut offset, mut size) = (0usize, 0usize);
+            for i in 0..4 {
+                if op & (1 << i) != 0 {
+                    offset |= (*delta.get(pos)? as usize) << (8 * i);
+                    pos += 1;
+                }
+            }
+            for i in 0..3 {
+                if op & (0x10 << i) != 0 {
+                    size |= (*delta.get(pos)? as usize) << (8 * i);
+                    pos += 1;
+                }
+            }
+            if size == 0 { size = 0x10000; }
+            out.extend_from_slice(base.get(offset..offset.checked_add(size)?)?);
+        } else if op != 0 {
+            out.extend_from_slice(delta.get(pos..pos + op as usize)?);
+            pos += op as usize;
+        } else {
+            return None;
+        }
+    }
+    (out.len() == result_len).then_some(out)
+}
+
+// Tree entries are "<mode> <name>\0<20-byte id>"
+fn tree_entry(data: &[u8], name: &str) -> Option<ObjectId> {
+    let mut rest = data;
+    while !rest.is_empty() {
+        let space = rest.iter().position(|b| *b == b' ')?;
+        let nul = space + rest[space..].iter().position(|b| *b == 0)?;
+        let id: ObjectId = rest.get(nul + 1..nul + 21)?.try_into().ok()?;
+        if &rest[space + 1..nul] == name.as_bytes() { return Some(id); }
+        rest = &rest[nul + 21..];
+    }
+    None
+}
+
+/// Per-line markers for `current` relative to `head`, indexed by line of `current`.
+fn line_marks(head: &str, current: &str) -> Vec<Option<LineChange>> {
+    let old: Vec<String> = head.lines().map(str::to_string).collect();
+    let new: Vec<String> = current.lines().map(str::to_string).collect();
+    let mut marks = vec![None; new.len()];
+    let mut deleted_above = false;
+    for row in crate::diff::align(&old, &new) {
+        match (row.kind, row.right) {
+            (crate::diff::RowKind::Removed, _) => deleted_above = true,
+            (crate::diff::RowKind::Changed, Some(r)) => marks[r] = Some(LineChange::Modified),
+            (crate::diff::RowKind::Added, Some(r)) => marks[r] = Some(LineChange::Added),
+            (_, Some(r)) if deleted_above => marks[r] = Some(LineChange::Deleted),
+            _ => {}
+        }
+        if row.right.is_some() { deleted_above = false; }
+    }
+    // Removed at the very end: mark the last line
+    if deleted_above && let Some(last) = marks.last_mut() {
+        last.get_or_insert(LineChange::Deleted);
+    }
+    marks
+}
+
+/// Git state of the open file: the branch, the file as committed at HEAD, and
+/// how each line of the working copy differs from it.
+pub(crate) struct FileStatus {
+    pub(crate) branch: String,
+    // Path within the repository, as git would name it
+    pub(crate) rel_path: Option<PathBuf>,
+    // None when the file isn't in HEAD (untracked or new)
+    pub(crate) head_text: Option<String>,
+    pub(crate) marks: Vec<Option<LineChange>>,
+}
+
+impl FileStatus {
+    /// None outside a git working tree. `text` is the working copy for text files;
+    /// pass None for images or files too big to diff.
+    pub(crate) fn load(path: &Path, text: Option<&str>) -> Option<FileStatus> {
+        let repo = Repo::discover(path)?;
+        let head = repo.head()?;
+        let branch = match (&head.branch, &head.commit) {
+            (Some(branch), _) => branch.clone(),
+            (None, Some(commit)) => format!("detached at {}", &to_hex(commit)[..7]),
+            (None, None) => "(no branch)".to_string(),
+        };
+        let rel_path = repo.relative_path(path);
+        let head_text = match (text, head.commit, &rel_path) {
+            (Some(_), Some(commit), Some(rel)) => repo
+                .blob_at(&commit, rel)
+                .map(|blob| String::from_utf8_lossy(&blob).into_owned()),
+            _ => None,
+        };
+        let marks = match (text, &head_text) {
+            (Some(current), Some(committed)) => line_marks(committed, current),
+            _ => Vec::new(),
+        };
+        Some(FileStatus { branch, rel_path, head_text, marks })
+    }
+
+    /// Run `load` on a background thread, since it reads packs and diffs the file.
+    pub(crate) fn load_in_background(ctx: &eframe::egui::Context, path: &Path, text: Option<String>) -> mpsc::Receiver<Option<FileStatus>> {
+        let (tx, rx) = mpsc::channel();
+        let (ctx, path) = (ctx.clone(), path.to_path_buf());
+        std::thread::spawn(move || {
+            let _ = tx.send(FileStatus::load(&path, text.as_deref()));
+            ctx.request_repaint();
+        });
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Copy instruction with a one-byte offset and size
+    fn copy(offset: u8, size: u8) -> [u8; 3] {
+        [0x80 | 0x01 | 0x10, offset, size]
+    }
+
+    #[test]
+    fn apply_delta_copies_and_inserts() {
+        let base = b"hello world";
+        let mut delta = vec![11, 9];
+        delta.extend(copy(6, 5));
+        delta.extend([4, b' ', b'x', b'y', b'z']);
+        assert_eq!(apply_delta(base, &delta).as_deref(), Some(&b"world xyz"[..]));
+    }
+
+    #[test]
+    fn apply_delta_size_zero_copies_64k() {
+        let base = vec![7u8; 0x10000];
+        // Base and result sizes as varints (0x10000), then a copy with no size bytes
+        let delta = [0x80, 0x80, 0x04, 0x80, 0x80, 0x04, 0x80];
+        assert_eq!(apply_delta(&base, &delta), Some(base));
+    }
+
+    #[test]
+    fn apply_delta_rejects_bad_input() {
+        let base = b"hello";
+        // Base size doesn't match
+        assert_eq!(apply_delta(base, &[4, 5, 0x91, 0, 5]), None);
+        // Copy past the end of the base
+        assert_eq!(apply_delta(base, &[5, 5, 0x91, 3, 5]), None);
+        // Result shorter than announced
+        assert_eq!(apply_delta(base, &[5, 6, 0x91, 0, 5]), None);
+        // Reserved opcode 0
+        assert_eq!(apply_delta(base, &[5, 0, 0]), None);
+        // Insert running past the end of the delta
+        assert_eq!(apply_delta(base, &[5, 3, 3, b'a']), None);
+    }
+
+    #[test]
+    fn tree_entry_finds_names() {
+        let (file, dir) = ([1u8; 20], [2u8; 20]);
+        let mut tree = b"100644 a.txt\0".to_vec();
+        tree.extend(file);
+        tree.extend(b"40000 src\0");
+        tree.extend(dir);
+        assert_eq!(tree_entry(&tree, "a.txt"), Some(file));
+        assert_eq!(tree_entry(&tree, "src"), Some(dir));
+        assert_eq!(tree_entry(&tree, "a"), None);
+        assert_eq!(tree_entry(&tree, "missing"), None);
+        assert_eq!(tree_entry(&tree[..tree.len() - 1], "src"), None);
+        assert_eq!(tree_entry(&[], "src"), None);
+    }
+
+    #[test]
+    fn parse_hex_round_trips() {
+        let hex = "0123456789abcdef0123456789abcdef01234567";
+        let id = parse_hex(hex).unwrap();
+        assert_eq!(id[0], 0x01);
+        assert_eq!(id[19], 0x67);
+        assert_eq!(to_hex(&id), hex);
+        assert_eq!(parse_hex(&hex.to_uppercase()), Some(id));
+    }
+
+    #[test]
+    fn parse_hex_rejects_bad_input() {
+        assert_eq!(parse_hex(""), None);
+        assert_eq!(parse_hex(&"a".repeat(39)), None);
+        assert_eq!(parse_hex(&"a".repeat(64)), None);
+        assert_eq!(parse_hex(&"g".repeat(40)), None);
+        // 40 bytes, but the pairs don't fall on character boundaries
+        assert_eq!(parse_hex(&format!("a{}a", "é".repeat(19))), None);
+    }
+}
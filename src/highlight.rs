@@ -42,6 +42,7 @@ pub(crate) fn append_with_search(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn token_highlight(
     job: &mut LayoutJob,
     text: &str,
@@ -77,61 +78,85 @@ pub(crate) fn token_highlight(
         "False","None","True","and","as","assert","async","await","break","class","continue","def","del","elif","else","except","finally","for","from","global","if","import","in","is","lambda","nonlocal","not","or","pass","raise","return","try","while","with","yield","match","case"
     ];
 
-    let mut buf = String::new();
-    for ch in text.chars() {
-        if ch.is_alphanumeric() || ch == '_' {
-            buf.push(ch);
+    for token in tokens(text) {
+        if token.starts_with(is_word_char) {
+            let lc = token.to_ascii_lowercase();
+            let color = if (ext == "rs" && keywords_rs.contains(&token))
+                || (ext == "py" && keywords_py.contains(&token))
+            {
+                kw_color
+            } else if lc == "true" || lc == "false" || lc == "null" || lc == "none" {
+                bool_color
+            } else if token.chars().all(|c| c.is_ascii_digit()) {
+                num_color
+            } else {
+                base_color
+            };
+            append_with_search(job, token, font_id.clone(), color, query, current_idx, counter);
         } else {
-            if !buf.is_empty() {
-                let lc = buf.to_ascii_lowercase();
-                let (color, _) = if ext == "rs" && keywords_rs.contains(&buf.as_str()) {
-                    (kw_color, true)
-                } else if ext == "py" && keywords_py.contains(&buf.as_str()) {
-                    (kw_color, true)
-                } else if lc == "true" || lc == "false" || lc == "null" || lc == "none" {
-                    (bool_color, true)
-                } else if buf.chars().all(|c| c.is_ascii_digit()) {
-                    (num_color, true)
-                } else {
-                    (base_color, false)
-                };
-                append_with_search(job, &buf, font_id.clone(), color, query, current_idx, counter);
-                buf.clear();
-            }
-            let color = match ch {
-                '(' | '[' | '{' => {
+            let color = match token {
+                "(" | "[" | "{" => {
                     let idx = ((*depth).max(0) as usize) % bracket_colors.len();
                     *depth = depth.saturating_add(1);
                     Some(bracket_colors[idx])
                 }
-                ')' | ']' | '}' => {
+                ")" | "]" | "}" => {
                     *depth = depth.saturating_sub(1);
                     let idx = ((*depth).max(0) as usize) % bracket_colors.len();
                     Some(bracket_colors[idx])
                 }
                 _ => None,
             };
-            let delim = ch.to_string();
-            append_with_search(job, &delim, font_id.clone(), color.unwrap_or(base_color), query, current_idx, counter);
+            append_with_search(job, token, font_id.clone(), color.unwrap_or(base_color), query, current_idx, counter);
         }
     }
-    if !buf.is_empty() {
-        let lc = buf.to_ascii_lowercase();
-        let (color, _) = if ext == "rs" && keywords_rs.contains(&buf.as_str()) {
-            (kw_color, true)
-        } else if ext == "py" && keywords_py.contains(&buf.as_str()) {
-            (kw_color, true)
-        } else if lc == "true" || lc == "false" || lc == "null" || lc == "none" {
-            (bool_color, true)
-        } else if buf.chars().all(|c| c.is_ascii_digit()) {
-            (num_color, true)
+}
+
+/// Change the format of the job text in `range` (bytes of `job.text`), splitting
+/// the sections it cuts through.
+pub(crate) fn restyle(job: &mut LayoutJob, range: std::ops::Range<usize>, style: impl Fn(&mut egui::TextFormat)) {
+    let mut sections = Vec::with_capacity(job.sections.len() + 2);
+    for section in job.sections.drain(..) {
+        let r = section.byte_range.clone();
+        if r.end <= range.start || r.start >= range.end {
+            sections.push(section);
+            continue;
+        }
+        let inner = r.start.max(range.start)..r.end.min(range.end);
+        let mut leading_space = section.leading_space;
+        for piece in [r.start..inner.start, inner.clone(), inner.end..r.end] {
+            if piece.is_empty() { continue; }
+            let mut format = section.format.clone();
+            if piece == inner { style(&mut format); }
+            sections.push(egui::text::LayoutSection { leading_space, byte_range: piece, format });
+            leading_space = 0.0;
+        }
+    }
+    job.sections = sections;
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Split a line into identifier/number words and single delimiter characters,
+/// the units syntax coloring works on.
+pub(crate) fn tokens(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+        let len = if is_word_char(first) {
+            rest.find(|c| !is_word_char(c)).unwrap_or(rest.len())
         } else {
-            (base_color, false)
+            first.len_utf8()
         };
-        append_with_search(job, &buf, font_id, color, query, current_idx, counter);
-    }
+        let (token, tail) = rest.split_at(len);
+        rest = tail;
+        Some(token)
+    })
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn append_highlighted(
     job: &mut LayoutJob,
     line: &str,
@@ -230,13 +255,13 @@ pub(crate) fn append_highlighted(
         }
         let comment_prefix = if ext == "rs" || ext == "js" { "//" } else if ext == "toml" { "#" } else { "" };
         let comment_prefix = if ext == "py" { "#" } else { comment_prefix };
-        if !comment_prefix.is_empty() {
-            if let Some(pos) = line.find(comment_prefix) {
-                append_highlighted(job, &line[..pos], "", query, font_id.clone(), base_color, do_syntax, depth, current_idx, counter, in_block_comment);
-                let fmt = egui::TextFormat { font_id: font_id.clone(), color: Color32::GRAY, ..Default::default() };
-                job.append(&line[pos..], 0.0, fmt);
-                return;
-            }
+        if !comment_prefix.is_empty()
+            && let Some(pos) = line.find(comment_prefix)
+        {
+            append_highlighted(job, &line[..pos], "", query, font_id.clone(), base_color, do_syntax, depth, current_idx, counter, in_block_comment);
+            let fmt = egui::TextFormat { font_id: font_id.clone(), color: Color32::GRAY, ..Default::default() };
+            job.append(&line[pos..], 0.0, fmt);
+            return;
         }
     }
 
@@ -249,7 +274,7 @@ pub(crate) fn append_highlighted(
                 if !buf.is_empty() { token_highlight(job, &buf, ext, font_id.clone(), base_color, query, do_syntax, depth, current_idx, counter); buf.clear(); }
                 buf.clear();
                 let mut s = String::from('"');
-                while let Some(c2) = chars.next() {
+                for c2 in chars.by_ref() {
                     s.push(c2);
                     if c2 == '"' { break; }
                 }
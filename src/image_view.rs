@@ -0,0 +1,363 @@
+use eframe::egui;
+use egui::{Pos2, Rect, Vec2};
+use crate::app::{Content, FileViewerApp};
+
+pub(crate) const MIN_ZOOM: f32 = 0.02;
+pub(crate) const MAX_ZOOM: f32 = 32.0;
+pub(crate) const ZOOM_STEP: f32 = 1.10;
+// Higher is snappier; roughly the inverse of the animation time constant
+const ZOOM_ANIM_SPEED: f32 = 18.0;
+// Screen size of one transparency checkerboard square
+const CHECKER_CELL: f32 = 8.0;
+
+/// Texture sampling used when the image is scaled.
+#[derive(Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum ImageFilter {
+    /// Nearest when magnified (crisp pixels), linear when minified
+    Auto,
+    Nearest,
+    Linear,
+}
+
+impl ImageFilter {
+    pub(crate) const ALL: [ImageFilter; 3] = [ImageFilter::Auto, ImageFilter::Nearest, ImageFilter::Linear];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            ImageFilter::Auto => "Auto",
+            ImageFilter::Nearest => "Nearest",
+            ImageFilter::Linear => "Linear",
+        }
+    }
+
+    pub(crate) fn texture_options(self) -> egui::TextureOptions {
+        match self {
+            ImageFilter::Auto => egui::TextureOptions {
+                magnification: egui::TextureFilter::Nearest,
+                minification: egui::TextureFilter::Linear,
+                ..egui::TextureOptions::LINEAR
+            },
+            ImageFilter::Nearest => egui::TextureOptions::NEAREST,
+            ImageFilter::Linear => egui::TextureOptions::LINEAR,
+        }
+    }
+}
+
+/// What is drawn behind transparent image pixels.
+#[derive(Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum TransparencyBg {
+    Checkerboard,
+    Black,
+    White,
+    Custom,
+    /// No backdrop; the panel background shows through
+    None,
+}
+
+impl TransparencyBg {
+    pub(crate) const ALL: [TransparencyBg; 5] = [
+        TransparencyBg::Checkerboard,
+        TransparencyBg::Black,
+        TransparencyBg::White,
+        TransparencyBg::Custom,
+        TransparencyBg::None,
+    ];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            TransparencyBg::Checkerboard => "Checkerboard",
+            TransparencyBg::Black => "Black",
+            TransparencyBg::White => "White",
+            TransparencyBg::Custom => "Custom",
+            TransparencyBg::None => "None",
+        }
+    }
+}
+
+/// Re-upload the current image texture after the filter mode changed.
+pub(crate) fn apply_filter(app: &mut FileViewerApp) {
+    let options = app.image_filter.texture_options();
+    if let Some(Content::Image(image)) = &mut app.content {
+        let pixels = image.pixels().clone();
+        image.texture.set(pixels, options);
+        if let Some(tiles) = &mut image.tiles {
+            tiles.clear();
+        }
+    }
+    crate::compare::apply_filter(app);
+}
+
+fn show_frame(app: &mut FileViewerApp, index: usize) {
+    let options = app.image_filter.texture_options();
+    if let Some(Content::Image(image)) = &mut app.content
+        && index < image.frames.len()
+        && index != image.current_frame
+    {
+        image.current_frame = index;
+        image.frame_elapsed = 0.0;
+        let pixels = image.pixels().clone();
+        image.texture.set(pixels, options);
+    }
+}
+
+/// Step the animation by `delta` frames (wrapping) and pause playback.
+pub(crate) fn step_frame(app: &mut FileViewerApp, delta: isize) {
+    let Some(Content::Image(image)) = &mut app.content else { return };
+    if !image.is_animated() { return; }
+    image.playing = false;
+    let count = image.frames.len() as isize;
+    let next = (image.current_frame as isize + delta).rem_euclid(count) as usize;
+    show_frame(app, next);
+}
+
+/// Rotate or flip the displayed image (all frames); the file is not modified.
+pub(crate) fn transform_image(app: &mut FileViewerApp, op: crate::transform::TransformOp) {
+    let options = app.image_filter.texture_options();
+    let Some(Content::Image(image)) = &mut app.content else { return };
+    for frame in &mut image.frames {
+        frame.transform(op);
+    }
+    image.transform = image.transform.then(op);
+    let pixels = image.pixels().clone();
+    image.texture.set(pixels, options);
+    app.image_pan = Vec2::ZERO;
+    app.hovered_pixel = None;
+}
+
+/// Ask for a destination and save the image with the current rotation/flip applied.
+pub(crate) fn save_transformed_copy(app: &mut FileViewerApp) {
+    let Some(Content::Image(image)) = &app.content else { return };
+    let Some(src) = app.current_path.clone() else { return };
+    let transform = image.transform;
+    let stem = src.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+    let ext = src.extension().and_then(|s| s.to_str()).unwrap_or("png");
+    let mut dialog = rfd::FileDialog::new().set_file_name(format!("{}-rotated.{}", stem, ext));
+    if let Some(dir) = src.parent() {
+        dialog = dialog.set_directory(dir);
+    }
+    if let Some(dest) = dialog.save_file()
+        && let Err(e) = crate::io::save_transformed(&src, &dest, transform)
+    {
+        app.error_message = Some(e);
+    }
+}
+
+pub(crate) fn toggle_playback(app: &mut FileViewerApp) {
+    if let Some(Content::Image(image)) = &mut app.content
+        && image.is_animated()
+    {
+        image.playing = !image.playing;
+    }
+}
+
+// Advance playback by the frame time and schedule the next repaint
+fn advance_animation(ui: &egui::Ui, app: &mut FileViewerApp) {
+    let Some(Content::Image(image)) = &mut app.content else { return };
+    if !image.is_animated() || !image.playing { return; }
+    image.frame_elapsed += ui.input(|i| i.stable_dt).min(0.1);
+    let mut index = image.current_frame;
+    let mut elapsed = image.frame_elapsed;
+    while elapsed >= image.frames[index].delay.as_secs_f32() {
+        elapsed -= image.frames[index].delay.as_secs_f32();
+        index = (index + 1) % image.frames.len();
+    }
+    let remaining = image.frames[index].delay.as_secs_f32() - elapsed;
+    ui.ctx().request_repaint_after(std::time::Duration::from_secs_f32(remaining.max(0.0)));
+    show_frame(app, index);
+    if let Some(Content::Image(image)) = &mut app.content {
+        image.frame_elapsed = elapsed;
+    }
+}
+
+// Re-render an SVG once zoom settles and the raster no longer matches the screen scale
+fn refresh_svg_raster(app: &mut FileViewerApp, scale: f32) {
+    let options = app.image_filter.texture_options();
+    let Some(Content::Image(image)) = &mut app.content else { return };
+    let Some(svg) = &mut image.svg else { return };
+    if (scale / svg.raster_scale - 1.0).abs() < 0.1 { return; }
+    // Remember the requested scale even if the budget caps it, so we don't re-render every frame
+    svg.raster_scale = scale;
+    let pixels = crate::transform::apply_view(crate::io::rasterize_svg(&svg.tree, scale), image.transform);
+    image.frames[0].pixels = pixels.clone();
+    image.texture.set(pixels, options);
+}
+
+pub(crate) fn paint_transparency_bg(ui: &egui::Ui, app: &mut FileViewerApp, painter: &egui::Painter, image_rect: Rect) {
+    let visible = image_rect.intersect(painter.clip_rect());
+    if !visible.is_positive() { return; }
+    let color = match app.transparency_bg {
+        TransparencyBg::None => return,
+        TransparencyBg::Black => egui::Color32::BLACK,
+        TransparencyBg::White => egui::Color32::WHITE,
+        TransparencyBg::Custom => {
+            let [r, g, b] = app.transparency_custom;
+            egui::Color32::from_rgb(r, g, b)
+        }
+        TransparencyBg::Checkerboard => {
+            // A 2x2 repeating texture; uv is anchored to the image so the pattern pans with it
+            let checker = app.checker_texture.get_or_insert_with(|| {
+                let light = egui::Color32::from_gray(204);
+                let dark = egui::Color32::from_gray(153);
+                let pattern = egui::ColorImage { size: [2, 2], pixels: vec![light, dark, dark, light] };
+                ui.ctx().load_texture("transparency-checker", pattern, egui::TextureOptions {
+                    wrap_mode: egui::TextureWrapMode::Repeat,
+                    ..egui::TextureOptions::NEAREST
+                })
+            });
+            let period = CHECKER_CELL * 2.0;
+            let uv = Rect::from_min_max(
+                ((visible.min - image_rect.min) / period).to_pos2(),
+                ((visible.max - image_rect.min) / period).to_pos2(),
+            );
+            painter.image(checker.id(), visible, uv, egui::Color32::WHITE);
+            return;
+        }
+    };
+    painter.rect_filled(visible, 0.0, color);
+}
+
+pub(crate) fn clamp_zoom(zoom: f32) -> f32 {
+    zoom.clamp(MIN_ZOOM, MAX_ZOOM)
+}
+
+/// Multiply the zoom, anchored at `anchor` (screen position) or the view center.
+pub(crate) fn zoom_by(app: &mut FileViewerApp, factor: f32, anchor: Option<Pos2>) {
+    // Leaving fit mode continues from the zoom currently on screen
+    let base = if app.image_fit && app.image_zoom_shown > 0.0 { app.image_zoom_shown } else { app.image_zoom };
+    set_zoom(app, base * factor, anchor);
+}
+
+pub(crate) fn set_zoom(app: &mut FileViewerApp, zoom: f32, anchor: Option<Pos2>) {
+    app.image_fit = false;
+    app.image_zoom = clamp_zoom(zoom);
+    app.image_zoom_anchor = anchor;
+    // Also the default for images without a remembered state
+    app.view_defaults.image_fit = false;
+    app.view_defaults.image_zoom = app.image_zoom;
+}
+
+fn fit_zoom(viewport: Vec2, size: Vec2) -> f32 {
+    let sx = if size.x > 0.0 { viewport.x / size.x } else { 1.0 };
+    let sy = if size.y > 0.0 { viewport.y / size.y } else { 1.0 };
+    let fit = sx.min(sy);
+    if fit.is_finite() && fit > 0.0 { clamp_zoom(fit) } else { 1.0 }
+}
+
+// Center axes where the image is smaller than the view, otherwise keep it covering the view
+fn clamp_pan(pan: Vec2, image: Vec2, viewport: Vec2) -> Vec2 {
+    let axis = |p: f32, img: f32, vp: f32| {
+        if img <= vp { 0.0 } else { let max = (img - vp) / 2.0; p.clamp(-max, max) }
+    };
+    egui::vec2(axis(pan.x, image.x, viewport.x), axis(pan.y, image.y, viewport.y))
+}
+
+/// Image canvas with drag panning, cursor-anchored wheel zoom and animated zoom changes.
+pub(crate) fn show(ui: &mut egui::Ui, app: &mut FileViewerApp) {
+    let Some(Content::Image(image)) = &app.content else { return };
+    let texture = image.texture.clone();
+    let size = image.display_size();
+    let (rect, resp) = ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+    advance_animation(ui, app);
+    // Side-by-side comparison gives each image half the canvas; zoom and pan apply to both
+    let side_by_side = app.compare.as_ref().is_some_and(|c| c.mode == crate::compare::CompareMode::SideBySide);
+    let view = if side_by_side {
+        Rect::from_min_max(rect.min, egui::pos2(rect.center().x, rect.max.y))
+    } else {
+        rect
+    };
+    // Anchor points in the right half act on the matching point of the left half
+    let to_view = |p: Pos2| if p.x > view.max.x { p - egui::vec2(view.width(), 0.0) } else { p };
+
+    if let Some(offset) = app.pending_scroll.take() {
+        app.image_pan = offset;
+    }
+
+    if resp.hovered() {
+        let (scroll, pointer) = ui.input(|i| (i.raw_scroll_delta.y, i.pointer.hover_pos()));
+        if scroll != 0.0 {
+            let factor = if scroll > 0.0 { ZOOM_STEP } else { 1.0 / ZOOM_STEP };
+            zoom_by(app, factor, pointer.map(to_view));
+        }
+    }
+    if resp.double_clicked() {
+        if app.image_fit {
+            set_zoom(app, 1.0, resp.interact_pointer_pos().map(to_view));
+        } else {
+            app.image_fit = true;
+            app.image_zoom_anchor = None;
+        }
+    }
+    if resp.dragged_by(egui::PointerButton::Primary) {
+        app.image_pan += resp.drag_delta();
+    }
+
+    // Ease the displayed zoom toward the target, keeping the anchor point fixed
+    let target = if app.image_fit { fit_zoom(view.size(), size) } else { app.image_zoom };
+    let prev = if app.image_zoom_shown > 0.0 { app.image_zoom_shown } else { target };
+    let dt = ui.input(|i| i.stable_dt).min(0.1);
+    let mut shown = prev * (target / prev).powf((dt * ZOOM_ANIM_SPEED).min(1.0));
+    if (shown / target - 1.0).abs() < 0.002 {
+        shown = target;
+        app.image_zoom_anchor = None;
+        refresh_svg_raster(app, shown * ui.ctx().pixels_per_point());
+    } else {
+        ui.ctx().request_repaint();
+    }
+    if shown != prev {
+        let anchor = app.image_zoom_anchor.unwrap_or(view.center()) - view.center();
+        app.image_pan = anchor - (anchor - app.image_pan) * (shown / prev);
+    }
+    app.image_zoom_shown = shown;
+
+    let image_size = size * shown;
+    app.image_pan = clamp_pan(app.image_pan, image_size, view.size());
+    app.scroll_offset = app.image_pan;
+    let image_rect = Rect::from_center_size(view.center() + app.image_pan, image_size);
+    let uv = Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+    let painter = ui.painter_at(view);
+    paint_transparency_bg(ui, app, &painter, image_rect);
+    painter.image(texture.id(), image_rect, uv, egui::Color32::WHITE);
+
+    // Full-resolution tiles once the downsampled preview would be magnified on screen
+    let options = app.image_filter.texture_options();
+    if app.full_res_tiles
+        && let Some(Content::Image(image)) = &mut app.content
+        && let Some(tiles) = &mut image.tiles
+        && shown * ui.ctx().pixels_per_point() > texture.size_vec2().x / size.x * 1.01
+    {
+        tiles.paint(ui.ctx(), &painter, image_rect, image.transform, options);
+    }
+    if app.compare.is_some() {
+        crate::compare::paint(ui, app, rect, view, image_rect);
+    }
+
+    // Pixel inspector: hovered color, click to copy, optional loupe
+    app.hovered_pixel = None;
+    if let Some(Content::Image(image)) = &app.content
+        && let Some(pointer) = resp.hover_pos()
+        && let Some(px) = crate::inspector::pixel_at(&image.frames[image.current_frame], image_rect, pointer)
+    {
+        // Report full-resolution coordinates for downsampled previews; the color comes from the preview
+        let mut hovered = px;
+        if image.full_size.is_some() {
+            let rel = (pointer - image_rect.min) / image_rect.size();
+            hovered.x = ((rel.x * size.x) as usize).min(size.x as usize - 1);
+            hovered.y = ((rel.y * size.y) as usize).min(size.y as usize - 1);
+        }
+        app.hovered_pixel = Some(hovered);
+        if resp.clicked() {
+            let hex = px.hex();
+            ui.ctx().copy_text(hex.clone());
+            app.copied_color = Some(hex);
+        }
+        if app.show_loupe && !resp.dragged() {
+            crate::inspector::draw_loupe(&painter, image.pixels(), px, pointer, ui.visuals().extreme_bg_color);
+        }
+    }
+
+    if resp.dragged() {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+    } else if resp.hovered() && (image_size.x > rect.width() || image_size.y > rect.height()) {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::Grab);
+    }
+}
@@ -0,0 +1,70 @@
+use eframe::egui;
+use egui::{Color32, ColorImage, Pos2, Rect};
+use crate::io::ImageFrame;
+
+// Loupe shows a square of LOUPE_RADIUS pixels on each side of the hovered pixel
+const LOUPE_RADIUS: i64 = 5;
+const LOUPE_CELL: f32 = 12.0;
+
+/// The image pixel under the pointer, with its unmultiplied color.
+#[derive(Clone, Copy)]
+pub(crate) struct HoveredPixel {
+    pub(crate) x: usize,
+    pub(crate) y: usize,
+    pub(crate) rgba: [u8; 4],
+}
+
+impl HoveredPixel {
+    pub(crate) fn hex(&self) -> String {
+        let [r, g, b, a] = self.rgba;
+        if a == 255 {
+            format!("#{:02X}{:02X}{:02X}", r, g, b)
+        } else {
+            format!("#{:02X}{:02X}{:02X}{:02X}", r, g, b, a)
+        }
+    }
+
+    pub(crate) fn color(&self) -> Color32 {
+        let [r, g, b, a] = self.rgba;
+        Color32::from_rgba_unmultiplied(r, g, b, a)
+    }
+}
+
+/// Map a screen position inside `image_rect` to the pixel it covers, with its decoded color.
+pub(crate) fn pixel_at(frame: &ImageFrame, image_rect: Rect, pos: Pos2) -> Option<HoveredPixel> {
+    if !image_rect.contains(pos) || image_rect.width() <= 0.0 || image_rect.height() <= 0.0 {
+        return None;
+    }
+    let [w, h] = frame.pixels.size;
+    let x = ((pos.x - image_rect.min.x) / image_rect.width() * w as f32).floor() as usize;
+    let y = ((pos.y - image_rect.min.y) / image_rect.height() * h as f32).floor() as usize;
+    let (x, y) = (x.min(w.saturating_sub(1)), y.min(h.saturating_sub(1)));
+    Some(HoveredPixel { x, y, rgba: frame.color_at(x, y)? })
+}
+
+/// Draw a magnified grid of the pixels around `hovered`, offset from the pointer.
+pub(crate) fn draw_loupe(painter: &egui::Painter, image: &ColorImage, hovered: HoveredPixel, pointer: Pos2, bg: Color32) {
+    let cells = (LOUPE_RADIUS * 2 + 1) as f32;
+    let side = cells * LOUPE_CELL;
+    let clip = painter.clip_rect();
+    // Prefer below-right of the pointer, flipping to stay inside the view
+    let mut min = pointer + egui::vec2(20.0, 20.0);
+    if min.x + side > clip.max.x { min.x = pointer.x - 20.0 - side; }
+    if min.y + side > clip.max.y { min.y = pointer.y - 20.0 - side; }
+    let frame = Rect::from_min_size(min, egui::vec2(side, side));
+    painter.rect_filled(frame.expand(2.0), 2.0, bg);
+
+    let [w, h] = image.size;
+    for dy in -LOUPE_RADIUS..=LOUPE_RADIUS {
+        for dx in -LOUPE_RADIUS..=LOUPE_RADIUS {
+            let (px, py) = (hovered.x as i64 + dx, hovered.y as i64 + dy);
+            if px < 0 || py < 0 || px >= w as i64 || py >= h as i64 { continue; }
+            let color = image.pixels[py as usize * w + px as usize];
+            let cell_min = min + egui::vec2((dx + LOUPE_RADIUS) as f32, (dy + LOUPE_RADIUS) as f32) * LOUPE_CELL;
+            painter.rect_filled(Rect::from_min_size(cell_min, egui::vec2(LOUPE_CELL, LOUPE_CELL)), 0.0, color);
+        }
+    }
+    let center = Rect::from_min_size(min + egui::vec2(LOUPE_RADIUS as f32, LOUPE_RADIUS as f32) * LOUPE_CELL, egui::vec2(LOUPE_CELL, LOUPE_CELL));
+    painter.rect_stroke(center, 0.0, egui::Stroke::new(1.5, Color32::WHITE), egui::StrokeKind::Outside);
+    painter.rect_stroke(frame, 2.0, egui::Stroke::new(1.0, Color32::GRAY), egui::StrokeKind::Outside);
+}
@@ -1,17 +1,202 @@
 use eframe::egui::ColorImage;
-use image::GenericImageView;
+use image::metadata::Orientation;
+use image::{AnimationDecoder, GenericImageView, ImageDecoder, ImageFormat};
+use resvg::usvg;
 use std::fs;
+use std::io::BufReader;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use crate::transform::ViewTransform;
 
 const MAX_IMAGE_TEXTURE_BYTES: usize = 128 * 1024 * 1024; // ~128 MB RGBA texture limit
+// Peak allocation allowed while decoding; larger images are decoded once, then downsampled
+const MAX_DECODE_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+// Browsers treat frame delays of 10 ms or less as 100 ms; match that so such GIFs don't spin
+const MAX_CLAMPED_FRAME_DELAY_MS: u64 = 10;
+const DEFAULT_FRAME_DELAY_MS: u64 = 100;
+// Longest side of a rasterized SVG, independent of zoom
+const MAX_SVG_RASTER_SIDE: f32 = 8192.0;
+// Bytes read from the start of a file to sniff its type
+const SNIFF_BYTES: usize = 1024;
 
-pub(crate) fn is_supported_image(path: &Path) -> bool {
+/// How a supported image format is decoded.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImageCodec {
+    Raster(ImageFormat),
+    Svg,
+}
+
+#[derive(PartialEq, Eq)]
+pub(crate) struct FormatInfo {
+    pub(crate) name: &'static str,
+    pub(crate) extensions: &'static [&'static str],
+    pub(crate) codec: ImageCodec,
+}
+
+// Single table behind image detection and the open dialog filters.
+// AVIF is not listed: the image crate only decodes it through the native dav1d library.
+pub(crate) const IMAGE_FORMATS: &[FormatInfo] = &[
+    FormatInfo { name: "PNG", extensions: &["png"], codec: ImageCodec::Raster(ImageFormat::Png) },
+    FormatInfo { name: "JPEG", extensions: &["jpg", "jpeg", "jpe", "jfif"], codec: ImageCodec::Raster(ImageFormat::Jpeg) },
+    FormatInfo { name: "GIF", extensions: &["gif"], codec: ImageCodec::Raster(ImageFormat::Gif) },
+    FormatInfo { name: "BMP", extensions: &["bmp", "dib"], codec: ImageCodec::Raster(ImageFormat::Bmp) },
+    FormatInfo { name: "WebP", extensions: &["webp"], codec: ImageCodec::Raster(ImageFormat::WebP) },
+    FormatInfo { name: "TIFF", extensions: &["tif", "tiff"], codec: ImageCodec::Raster(ImageFormat::Tiff) },
+    // The ICO decoder ignores the resource type, so cursors decode the same way
+    FormatInfo { name: "ICO/CUR", extensions: &["ico", "cur"], codec: ImageCodec::Raster(ImageFormat::Ico) },
+    FormatInfo { name: "TGA", extensions: &["tga"], codec: ImageCodec::Raster(ImageFormat::Tga) },
+    FormatInfo { name: "PNM", extensions: &["pbm", "pgm", "ppm", "pnm", "pam"], codec: ImageCodec::Raster(ImageFormat::Pnm) },
+    FormatInfo { name: "QOI", extensions: &["qoi"], codec: ImageCodec::Raster(ImageFormat::Qoi) },
+    FormatInfo { name: "DDS", extensions: &["dds"], codec: ImageCodec::Raster(ImageFormat::Dds) },
+    FormatInfo { name: "HDR", extensions: &["hdr"], codec: ImageCodec::Raster(ImageFormat::Hdr) },
+    FormatInfo { name: "OpenEXR", extensions: &["exr"], codec: ImageCodec::Raster(ImageFormat::OpenExr) },
+    FormatInfo { name: "SVG", extensions: &["svg", "svgz"], codec: ImageCodec::Svg },
+];
+
+pub(crate) const TEXT_EXTENSIONS: &[&str] = &["txt", "rs", "py", "toml", "md", "json", "js", "html", "css"];
+
+/// Which viewer a file opens in, decided by `detect_content_kind`.
+#[derive(Clone, Copy)]
+pub(crate) enum ContentKind {
+    Image(&'static FormatInfo),
+    Text,
+}
+
+pub(crate) struct ImageFrame {
+    pub(crate) pixels: ColorImage,
+    // The decoded colors when some pixels are translucent, whose premultiplied `pixels` are rounded
+    pub(crate) rgba: Option<Vec<[u8; 4]>>,
+    pub(crate) delay: Duration,
+}
+
+impl ImageFrame {
+    fn new(rgba: &image::RgbaImage, delay: Duration) -> Self {
+        let size = [rgba.width() as usize, rgba.height() as usize];
+        let pixels = ColorImage::from_rgba_unmultiplied(size, rgba.as_raw());
+        let translucent = rgba.pixels().any(|p| p.0[3] < 255);
+        Self { pixels, rgba: translucent.then(|| rgba.pixels().map(|p| p.0).collect()), delay }
+    }
+
+    /// Unmultiplied color of the pixel at (x, y), as decoded.
+    pub(crate) fn color_at(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+        let index = y * self.pixels.size[0] + x;
+        match &self.rgba {
+            Some(rgba) => rgba.get(index).copied(),
+            None => self.pixels.pixels.get(index).map(|c| c.to_srgba_unmultiplied()),
+        }
+    }
+
+    /// Rotate or flip the frame.
+    pub(crate) fn transform(&mut self, op: crate::transform::TransformOp) {
+        let size = self.pixels.size;
+        (self.pixels.size, self.pixels.pixels) = crate::transform::remap(&self.pixels.pixels, size, op);
+        if let Some(rgba) = &mut self.rgba {
+            *rgba = crate::transform::remap(rgba, size, op).1;
+        }
+    }
+}
+
+/// Decoded image: a single frame for stills, every frame for animations.
+pub(crate) struct DecodedImage {
+    pub(crate) frames: Vec<ImageFrame>,
+    // Frames were dropped to stay within MAX_IMAGE_TEXTURE_BYTES
+    pub(crate) truncated: bool,
+    // Vector source for SVGs, kept so they can be re-rasterized at the current zoom
+    pub(crate) svg: Option<usvg::Tree>,
+    // Full-resolution size when `frames` hold a downsampled preview
+    pub(crate) full_size: Option<[usize; 2]>,
+}
+
+pub(crate) fn image_format(path: &Path) -> Option<&'static FormatInfo> {
     let ext = path
         .extension()
         .and_then(|s| s.to_str())
         .unwrap_or("")
         .to_lowercase();
-    matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp")
+    IMAGE_FORMATS.iter().find(|f| f.extensions.contains(&ext.as_str()))
+}
+
+/// Decide how to open a file from its leading bytes, falling back to the extension.
+/// A PNG without an extension opens as an image; a `.png` holding HTML opens as text.
+pub(crate) fn detect_content_kind(path: &Path) -> ContentKind {
+    let head = read_head(path).unwrap_or_default();
+    if let Some(info) = sniff_image(&head) {
+        return ContentKind::Image(info);
+    }
+    match image_format(path) {
+        // TGA has no magic number and SVGZ is gzip-compressed, so trust the extension
+        Some(info) if !has_magic(info) => ContentKind::Image(info),
+        Some(info) if !looks_like_text(&head) => ContentKind::Image(info),
+        _ => ContentKind::Text,
+    }
+}
+
+fn read_head(path: &Path) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    fs::File::open(path)?.take(SNIFF_BYTES as u64).read_to_end(&mut head)?;
+    Ok(head)
+}
+
+fn sniff_image(head: &[u8]) -> Option<&'static FormatInfo> {
+    if looks_like_svg(head) {
+        return IMAGE_FORMATS.iter().find(|f| f.codec == ImageCodec::Svg);
+    }
+    let format = match image::guess_format(head) {
+        // "BM" and "P1".."P7" are short enough to start plain text; check more of the header
+        Ok(ImageFormat::Bmp) if head.get(6..10) != Some(&[0, 0, 0, 0]) => return None,
+        Ok(ImageFormat::Pnm) if !head.get(2).is_some_and(u8::is_ascii_whitespace) => return None,
+        Ok(format) => format,
+        // Cursors share the ICO layout but use resource type 2
+        Err(_) if head.starts_with(&[0, 0, 2, 0]) => ImageFormat::Ico,
+        Err(_) => return None,
+    };
+    IMAGE_FORMATS.iter().find(|f| f.codec == ImageCodec::Raster(format))
+}
+
+fn looks_like_svg(head: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(head).to_lowercase();
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    text.starts_with("<svg")
+        || ((text.starts_with("<?xml") || text.starts_with("<!--") || text.starts_with("<!doctype svg")) && text.contains("<svg"))
+}
+
+fn has_magic(info: &FormatInfo) -> bool {
+    !matches!(info.codec, ImageCodec::Svg | ImageCodec::Raster(ImageFormat::Tga))
+}
+
+// No NUL bytes and valid UTF-8, allowing a multi-byte character cut off at the end
+fn looks_like_text(head: &[u8]) -> bool {
+    !head.contains(&0)
+        && match std::str::from_utf8(head) {
+            Ok(_) => true,
+            Err(e) => e.error_len().is_none(),
+        }
+}
+
+pub(crate) fn open_file_dialog() -> Option<PathBuf> {
+    let images: Vec<&str> = IMAGE_FORMATS.iter().flat_map(|f| f.extensions.iter().copied()).collect();
+    let all: Vec<&str> = TEXT_EXTENSIONS.iter().copied().chain(images.iter().copied()).collect();
+    rfd::FileDialog::new()
+        .add_filter("All Supported", &all)
+        .add_filter("Images", &images)
+        .add_filter("Text/Source", TEXT_EXTENSIONS)
+        // Unknown extensions are sniffed on open
+        .add_filter("All Files", &["*"])
+        .pick_file()
+}
+
+/// Image reader with the format fixed to the detected one (so e.g. `.cur` decodes as ICO).
+pub(crate) fn image_reader(path: &Path, info: &FormatInfo) -> Result<image::ImageReader<BufReader<fs::File>>, String> {
+    let mut reader = image::ImageReader::open(path).map_err(|e| format!("Failed to open image: {}", e))?;
+    if let ImageCodec::Raster(format) = info.codec {
+        reader.set_format(format);
+    }
+    let mut limits = image::Limits::default();
+    limits.max_alloc = Some(MAX_DECODE_BYTES);
+    reader.limits(limits);
+    Ok(reader)
 }
 
 pub(crate) fn load_text(path: &Path) -> Result<(String, bool, usize), String> {
@@ -22,48 +207,220 @@ pub(crate) fn load_text(path: &Path) -> Result<(String, bool, usize), String> {
     Ok((text, lossy, lines))
 }
 
-pub(crate) fn load_image(path: &Path) -> Result<ColorImage, String> {
-    // Pre-check dimensions to estimate texture memory before decoding
-    if let Ok((w, h)) = image::image_dimensions(path) {
-        let est_bytes: usize = (w as usize)
-            .saturating_mul(h as usize)
-            .saturating_mul(4);
-        if est_bytes > MAX_IMAGE_TEXTURE_BYTES {
-            return Err(format!(
-                "Image too large: {}x{} (~{:.1} MB RGBA). Limit ~{:.0} MB",
-                w,
-                h,
-                est_bytes as f64 / (1024.0 * 1024.0),
-                MAX_IMAGE_TEXTURE_BYTES as f64 / (1024.0 * 1024.0)
-            ));
-        }
+/// Decode an image for display. Images over the texture budget or `max_side`
+/// (the GPU's texture size limit) are downsampled to a preview.
+pub(crate) fn load_image(path: &Path, info: &FormatInfo, max_side: usize) -> Result<DecodedImage, String> {
+    if info.codec == ImageCodec::Svg {
+        let tree = load_svg(path)?;
+        let pixels = rasterize_svg(&tree, 1.0);
+        let frames = vec![ImageFrame { pixels, rgba: None, delay: Duration::ZERO }];
+        return Ok(DecodedImage { frames, truncated: false, svg: Some(tree), full_size: None });
+    }
+
+    if let Some(animated) = load_animation(path, info, max_side)? {
+        return Ok(animated);
     }
 
-    let img = image::open(path).map_err(|e| format!("Failed to open image: {}", e))?;
+    let img = decode_oriented(path, info, MAX_DECODE_BYTES)?;
     let (width, height) = img.dimensions();
-    let rgba = img.to_rgba8();
-    let pixels = rgba.into_flat_samples();
-    Ok(ColorImage::from_rgba_unmultiplied([
-        width as _,
-        height as _,
-    ], pixels.as_slice()))
+    let scale = preview_scale(width, height, max_side);
+    if scale >= 1.0 {
+        let frames = vec![ImageFrame::new(&to_rgba(&img), Duration::ZERO)];
+        return Ok(DecodedImage { frames, truncated: false, svg: None, full_size: None });
+    }
+    let (pw, ph) = scaled_size(width, height, scale);
+    let preview = img.thumbnail_exact(pw, ph);
+    Ok(DecodedImage {
+        frames: vec![ImageFrame::new(&to_rgba(&preview), Duration::ZERO)],
+        truncated: false,
+        svg: None,
+        full_size: Some([width as usize, height as usize]),
+    })
+}
+
+// Largest scale (at most 1) that fits the texture budget and the GPU's maximum texture side
+fn preview_scale(width: u32, height: u32, max_side: usize) -> f64 {
+    let pixels = (width as f64 * height as f64).max(1.0);
+    let budget = ((MAX_IMAGE_TEXTURE_BYTES / 4) as f64 / pixels).sqrt();
+    let side = max_side as f64 / width.max(height).max(1) as f64;
+    budget.min(side).min(1.0)
+}
+
+fn scaled_size(width: u32, height: u32, scale: f64) -> (u32, u32) {
+    (((width as f64 * scale) as u32).max(1), ((height as f64 * scale) as u32).max(1))
+}
+
+/// Pixels of a region of a full-resolution image, converted for upload as a texture.
+pub(crate) fn region_pixels(img: &image::DynamicImage, x: u32, y: u32, width: u32, height: u32) -> ColorImage {
+    to_color_image(&img.crop_imm(x, y, width, height))
+}
+
+fn to_color_image(img: &image::DynamicImage) -> ColorImage {
+    let rgba = to_rgba(img);
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    ColorImage::from_rgba_unmultiplied(size, rgba.as_raw())
+}
+
+fn to_rgba(img: &image::DynamicImage) -> image::RgbaImage {
+    match img.color() {
+        image::ColorType::Rgb32F | image::ColorType::Rgba32F => tone_map(img),
+        _ => img.to_rgba8(),
+    }
+}
+
+/// Decode a small preview whose longest side is at most `size`, for the gallery.
+pub(crate) fn load_thumbnail(path: &Path, size: u32) -> Result<image::RgbaImage, String> {
+    let ContentKind::Image(info) = detect_content_kind(path) else {
+        return Err("Not an image".to_string());
+    };
+    if info.codec == ImageCodec::Svg {
+        let tree = load_svg(path)?;
+        let scale = size as f32 / tree.size().width().max(tree.size().height());
+        let pixels = rasterize_svg(&tree, scale);
+        let raw = pixels.pixels.iter().flat_map(|c| c.to_srgba_unmultiplied()).collect();
+        return image::RgbaImage::from_raw(pixels.size[0] as u32, pixels.size[1] as u32, raw)
+            .ok_or_else(|| "Failed to render SVG".to_string());
+    }
+    let img = decode_oriented(path, info, MAX_DECODE_BYTES)?;
+    Ok(to_rgba(&img.thumbnail(size, size)))
+}
+
+// Reinhard tone mapping of linear HDR/EXR radiance to 8-bit sRGB
+fn tone_map(img: &image::DynamicImage) -> image::RgbaImage {
+    let hdr = img.to_rgba32f();
+    let encode = |v: f32| {
+        let v = v.max(0.0);
+        let mapped = v / (1.0 + v);
+        let srgb = if mapped <= 0.003_130_8 { 12.92 * mapped } else { 1.055 * mapped.powf(1.0 / 2.4) - 0.055 };
+        (srgb * 255.0).round().clamp(0.0, 255.0) as u8
+    };
+    image::RgbaImage::from_fn(hdr.width(), hdr.height(), |x, y| {
+        let [r, g, b, a] = hdr.get_pixel(x, y).0;
+        image::Rgba([encode(r), encode(g), encode(b), (a.clamp(0.0, 1.0) * 255.0).round() as u8])
+    })
+}
+
+fn load_svg(path: &Path) -> Result<usvg::Tree, String> {
+    // System fonts are scanned once and shared by every SVG opened afterwards
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    let fonts = FONTS.get_or_init(|| {
+        let mut db = usvg::fontdb::Database::new();
+        db.load_system_fonts();
+        Arc::new(db)
+    });
+    let data = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let options = usvg::Options {
+        resources_dir: path.parent().map(Path::to_path_buf),
+        fontdb: fonts.clone(),
+        ..Default::default()
+    };
+    usvg::Tree::from_data(&data, &options).map_err(|e| format!("Failed to parse SVG: {}", e))
+}
+
+/// Render an SVG at `scale` times its intrinsic size, limited by the texture budget.
+pub(crate) fn rasterize_svg(tree: &usvg::Tree, scale: f32) -> ColorImage {
+    let size = tree.size();
+    let (w, h) = (size.width().max(1.0), size.height().max(1.0));
+    let budget_scale = ((MAX_IMAGE_TEXTURE_BYTES / 4) as f32 / (w * h)).sqrt();
+    let side_scale = MAX_SVG_RASTER_SIDE / w.max(h);
+    let scale = scale.min(budget_scale).min(side_scale).max(f32::EPSILON);
+    let (pw, ph) = ((w * scale).ceil().max(1.0) as u32, (h * scale).ceil().max(1.0) as u32);
+    let Some(mut pixmap) = resvg::tiny_skia::Pixmap::new(pw, ph) else {
+        return ColorImage::new([1, 1], eframe::egui::Color32::TRANSPARENT);
+    };
+    resvg::render(tree, resvg::tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+    ColorImage::from_rgba_premultiplied([pw as usize, ph as usize], pixmap.data())
+}
+
+/// Decode the full-resolution pixels of a still for tiles; run off the UI thread.
+pub(crate) fn decode_for_tiles(path: &Path, info: &FormatInfo) -> Result<image::DynamicImage, String> {
+    decode_oriented(path, info, MAX_DECODE_BYTES)
+}
+
+// Decode a still image and apply its EXIF orientation, so phone photos show upright
+fn decode_oriented(path: &Path, info: &FormatInfo, max_alloc: u64) -> Result<image::DynamicImage, String> {
+    let mut reader = image_reader(path, info)?;
+    let mut limits = image::Limits::default();
+    limits.max_alloc = Some(max_alloc);
+    reader.limits(limits);
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(|e| format!("Failed to open image: {}", e))?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut img = image::DynamicImage::from_decoder(decoder)
+        .map_err(|e| format!("Failed to open image: {}", e))?;
+    img.apply_orientation(orientation);
+    Ok(img)
+}
+
+/// Write a copy of `src` with the view rotation/flip applied (first frame only for animations).
+pub(crate) fn save_transformed(src: &Path, dest: &Path, transform: ViewTransform) -> Result<(), String> {
+    let ContentKind::Image(info) = detect_content_kind(src) else {
+        return Err("Not an image".to_string());
+    };
+    let mut img = decode_oriented(src, info, MAX_DECODE_BYTES)?;
+    img.apply_orientation(transform.to_orientation());
+    img.save(dest).map_err(|e| format!("Failed to save image: {}", e))
+}
+
+// Decode all frames of an animated GIF/WebP; Ok(None) for stills and other formats
+fn load_animation(path: &Path, info: &FormatInfo, max_side: usize) -> Result<Option<DecodedImage>, String> {
+    let open = || -> Result<BufReader<fs::File>, String> {
+        fs::File::open(path).map(BufReader::new).map_err(|e| format!("Failed to open image: {}", e))
+    };
+    let frames = match info.codec {
+        ImageCodec::Raster(ImageFormat::Gif) => {
+            let decoder = image::codecs::gif::GifDecoder::new(open()?).map_err(|e| format!("Failed to decode GIF: {}", e))?;
+            decoder.into_frames()
+        }
+        ImageCodec::Raster(ImageFormat::WebP) => {
+            let decoder = image::codecs::webp::WebPDecoder::new(open()?).map_err(|e| format!("Failed to decode WebP: {}", e))?;
+            if !decoder.has_animation() { return Ok(None); }
+            decoder.into_frames()
+        }
+        _ => return Ok(None),
+    };
+
+    let mut decoded = DecodedImage { frames: Vec::new(), truncated: false, svg: None, full_size: None };
+    let mut scale = 1.0;
+    let mut total_bytes = 0usize;
+    for frame in frames {
+        let frame = frame.map_err(|e| format!("Failed to decode frame: {}", e))?;
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        let delay_ms = if denom == 0 { 0 } else { u64::from(numer) / u64::from(denom) };
+        let delay_ms = if delay_ms <= MAX_CLAMPED_FRAME_DELAY_MS { DEFAULT_FRAME_DELAY_MS } else { delay_ms };
+        let mut buffer = frame.into_buffer();
+        // Every frame is a full canvas, so the first one decides the preview scale
+        if decoded.frames.is_empty() {
+            scale = preview_scale(buffer.width(), buffer.height(), max_side);
+            if scale < 1.0 {
+                decoded.full_size = Some([buffer.width() as usize, buffer.height() as usize]);
+            }
+        }
+        if scale < 1.0 {
+            let (pw, ph) = scaled_size(buffer.width(), buffer.height(), scale);
+            buffer = image::imageops::thumbnail(&buffer, pw, ph);
+        }
+        let frame_bytes = (buffer.width() as usize)
+            .saturating_mul(buffer.height() as usize)
+            .saturating_mul(4);
+        if !decoded.frames.is_empty() && total_bytes.saturating_add(frame_bytes) > MAX_IMAGE_TEXTURE_BYTES {
+            decoded.truncated = true;
+            break;
+        }
+        total_bytes = total_bytes.saturating_add(frame_bytes);
+        decoded.frames.push(ImageFrame::new(&buffer, Duration::from_millis(delay_ms)));
+    }
+    Ok(Some(decoded))
 }
 
-pub(crate) fn neighbor_image(path: &Path, forward: bool) -> Option<PathBuf> {
-    let parent = path.parent()?;
-    let mut images: Vec<PathBuf> = std::fs::read_dir(parent).ok()?
+/// Image files directly inside `dir` (by content, like opening them), sorted by path.
+pub(crate) fn list_images(dir: &Path) -> Vec<PathBuf> {
+    let Ok(read) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut images: Vec<PathBuf> = read
         .filter_map(|e| e.ok().map(|e| e.path()))
-        .filter(|p| p.is_file() && is_supported_image(p))
+        .filter(|p| p.is_file() && matches!(detect_content_kind(p), ContentKind::Image(_)))
         .collect();
-    if images.is_empty() { return None; }
     images.sort();
-    let current_name = path.file_name()?;
-    let idx = images.iter().position(|p| p.file_name() == Some(current_name))?;
-    if images.len() <= 1 { return None; }
-    let next_idx = if forward {
-        (idx + 1) % images.len()
-    } else {
-        (idx + images.len() - 1) % images.len()
-    };
-    images.get(next_idx).cloned()
+    images
 }
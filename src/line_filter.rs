@@ -0,0 +1,129 @@
+use eframe::egui;
+use egui::text::LayoutJob;
+use egui::{Color32, TextFormat};
+use std::ops::Range;
+use crate::app::{Content, FileViewerApp};
+use crate::search::Matcher;
+
+// Most context lines offered around each match
+pub(crate) const MAX_CONTEXT: usize = 20;
+const CONTEXT_COLOR: Color32 = Color32::from_rgb(140, 140, 140);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Row {
+    /// A line that passed the filter
+    Hit(usize),
+    /// A line shown only as context around a hit
+    Context(usize),
+    /// "--" between non-adjacent groups, as grep prints it
+    Gap,
+}
+
+/// Lines of the open text that match (or, inverted, don't match) the find
+/// query, with context. Rebuilt when the query or options change.
+pub(crate) struct LineFilter {
+    query: String,
+    context: usize,
+    invert: bool,
+    lines: Vec<Range<usize>>,
+    rows: Vec<Row>,
+    pub(crate) hits: usize,
+}
+
+impl LineFilter {
+    fn build(text: &str, query: &str, context: usize, invert: bool) -> Self {
+        let matcher = Matcher::new(query);
+        let lines: Vec<Range<usize>> = text
+            .lines()
+            .map(|line| {
+                let start = line.as_ptr() as usize - text.as_ptr() as usize;
+                start..start + line.len()
+            })
+            .collect();
+        let passes: Vec<bool> = lines
+            .iter()
+            .map(|r| matcher.as_ref().is_some_and(|m| m.is_match(&text[r.clone()])) != invert)
+            .collect();
+        let mut rows = Vec::new();
+        // One past the last line emitted, to avoid repeating overlapping context
+        let mut next = 0;
+        for (i, _) in passes.iter().enumerate().filter(|(_, pass)| **pass) {
+            let from = i.saturating_sub(context).max(next);
+            if from > next && !rows.is_empty() {
+                rows.push(Row::Gap);
+            }
+            let to = (i + context + 1).min(lines.len());
+            rows.extend((from..to).map(|j| if passes[j] { Row::Hit(j) } else { Row::Context(j) }));
+            next = next.max(to);
+        }
+        let hits = passes.iter().filter(|p| **p).count();
+        Self { query: query.to_string(), context, invert, lines, rows, hits }
+    }
+
+    fn is_current(&self, query: &str, context: usize, invert: bool) -> bool {
+        self.query == query && self.context == context && self.invert == invert
+    }
+}
+
+/// Whether the filtered view replaces the full text view this frame.
+pub(crate) fn is_active(app: &FileViewerApp) -> bool {
+    app.filter_lines && !app.search_query.is_empty() && matches!(app.content, Some(Content::Text(_)))
+}
+
+/// Draw only the filtered lines, keeping their original numbers; clicking a
+/// line returns to the full view scrolled to it.
+pub(crate) fn show(ui: &mut egui::Ui, app: &mut FileViewerApp) {
+    let Some(Content::Text(text)) = &app.content else { return };
+    let (context, invert) = (app.filter_context.min(MAX_CONTEXT), app.filter_invert);
+    if !app.line_filter.as_ref().is_some_and(|f| f.is_current(&app.search_query, context, invert)) {
+        app.line_filter = Some(LineFilter::build(text, &app.search_query, context, invert));
+    }
+    let Some(filter) = &app.line_filter else { return };
+
+    let mut font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    font_id.size = (font_id.size * app.text_zoom).clamp(8.0, 48.0);
+    let text_color = ui.visuals().text_color();
+    let row_height = ui.fonts(|f| f.row_height(&font_id));
+    let digits = filter.lines.len().max(1).to_string().len().max(4);
+    let mut clicked = None;
+
+    egui::Frame::group(ui.style()).show(ui, |ui| {
+        if filter.rows.is_empty() {
+            ui.label(if invert { "Every line matches." } else { "No matching lines." });
+            return;
+        }
+        egui::ScrollArea::both().auto_shrink([false, false]).show_rows(ui, row_height, filter.rows.len(), |ui, range| {
+            for row in &filter.rows[range] {
+                let (line, color) = match *row {
+                    Row::Hit(line) => (line, text_color),
+                    Row::Context(line) => (line, CONTEXT_COLOR),
+                    Row::Gap => {
+                        ui.label(egui::RichText::new("--").font(font_id.clone()).color(Color32::GRAY));
+                        continue;
+                    }
+                };
+                let mut job = LayoutJob::default();
+                let separator = if matches!(row, Row::Hit(_)) { ':' } else { '-' };
+                job.append(&format!("{:>digits$}{} ", line + 1, separator), 0.0, TextFormat { font_id: font_id.clone(), color: Color32::GRAY, ..Default::default() });
+                let mut counter = 0;
+                let query = if invert { "" } else { app.search_query.as_str() };
+                crate::highlight::append_with_search(&mut job, &text[filter.lines[line].clone()], font_id.clone(), color, query, usize::MAX, &mut counter);
+                job.wrap.max_width = f32::INFINITY;
+                let resp = ui.add(egui::Label::new(job).sense(egui::Sense::click()));
+                if resp.on_hover_text("Show in full file").clicked() {
+                    clicked = Some(line);
+                }
+            }
+        });
+    });
+
+    if let Some(line) = clicked {
+        app.filter_lines = false;
+        app.goto_line = Some(line);
+        // Select the first match at or after the line so the find bar continues from there
+        if let Some(matcher) = Matcher::new(&app.search_query) {
+            let before: usize = text.lines().take(line).map(|l| matcher.find_iter(l).len()).sum();
+            app.search_current = before.min(app.search_count.saturating_sub(1));
+        }
+    }
+}
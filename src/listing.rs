@@ -0,0 +1,201 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use crate::app::FileViewerApp;
+
+// Subfolder changes don't touch the top folder's mtime, so recursive listings expire
+const RECURSIVE_LISTING_TTL: Duration = Duration::from_secs(5);
+// Stop walking huge trees in recursive mode
+const MAX_LISTED_FILES: usize = 20_000;
+
+/// Order that Prev/Next steps through a folder.
+#[derive(Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum NeighborSort {
+    /// Names with numbers compared by value: img2 before img10
+    Natural,
+    /// Oldest first
+    Modified,
+    /// Smallest first
+    Size,
+}
+
+impl NeighborSort {
+    pub(crate) const ALL: [NeighborSort; 3] = [NeighborSort::Natural, NeighborSort::Modified, NeighborSort::Size];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            NeighborSort::Natural => "Name",
+            NeighborSort::Modified => "Date Modified",
+            NeighborSort::Size => "Size",
+        }
+    }
+}
+
+/// Which files count as neighbors: images step through images, text files
+/// through text files with the same extension (e.g. every `.log` in a folder).
+/// Images are told apart by content, as when opening them.
+#[derive(Clone, PartialEq, Eq)]
+enum ListFilter {
+    Images,
+    Extension(String),
+}
+
+impl ListFilter {
+    fn for_file(path: &Path) -> Self {
+        if is_image(path) {
+            ListFilter::Images
+        } else {
+            ListFilter::Extension(extension(path))
+        }
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        match self {
+            ListFilter::Images => is_image(path),
+            ListFilter::Extension(ext) => extension(path) == *ext && !is_image(path),
+        }
+    }
+}
+
+fn is_image(path: &Path) -> bool {
+    matches!(crate::io::detect_content_kind(path), crate::io::ContentKind::Image(_))
+}
+
+fn extension(path: &Path) -> String {
+    path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase()
+}
+
+/// Cached, sorted listing of the files Prev/Next steps through.
+pub(crate) struct DirListing {
+    root: PathBuf,
+    filter: ListFilter,
+    sort: NeighborSort,
+    recursive: bool,
+    root_mtime: Option<SystemTime>,
+    listed_at: Instant,
+    files: Vec<PathBuf>,
+}
+
+impl DirListing {
+    fn is_valid_for(&self, current: &Path, filter: &ListFilter, sort: NeighborSort, recursive: bool) -> bool {
+        if self.filter != *filter || self.sort != sort || self.recursive != recursive {
+            return false;
+        }
+        // A recursive listing keeps its root while stepping into subfolders
+        let in_root = if recursive {
+            current.starts_with(&self.root) && self.files.iter().any(|p| p == current)
+        } else {
+            current.parent() == Some(self.root.as_path())
+        };
+        in_root
+            && dir_mtime(&self.root) == self.root_mtime
+            && (!recursive || self.listed_at.elapsed() < RECURSIVE_LISTING_TTL)
+    }
+}
+
+fn dir_mtime(dir: &Path) -> Option<SystemTime> {
+    fs::metadata(dir).and_then(|m| m.modified()).ok()
+}
+
+/// Compare names so that digit runs are ordered by value ("img2" < "img10"), ignoring case.
+pub(crate) fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_digits = |it: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = it.next_if(char::is_ascii_digit) {
+                        digits.push(c);
+                    }
+                    digits
+                };
+                let (da, db) = (take_digits(&mut a), take_digits(&mut b));
+                let (ta, tb) = (da.trim_start_matches('0'), db.trim_start_matches('0'));
+                // Longer numbers are larger; equal lengths compare digit by digit
+                let ord = ta.len().cmp(&tb.len()).then_with(|| ta.cmp(tb)).then_with(|| da.len().cmp(&db.len()));
+                if ord != Ordering::Equal { return ord; }
+            }
+            (Some(x), Some(y)) => {
+                let ord = x.to_lowercase().cmp(y.to_lowercase()).then(x.cmp(&y));
+                if ord != Ordering::Equal { return ord; }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+fn list_files(root: &Path, filter: &ListFilter, recursive: bool) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(read) = fs::read_dir(&dir) else { continue };
+        for entry in read.flatten() {
+            let path = entry.path();
+            // Symlinked folders are not followed, which also rules out cycles
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            if is_dir {
+                let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                if recursive && !hidden {
+                    dirs.push(path);
+                }
+            } else if path.is_file() && filter.matches(&path) {
+                files.push(path);
+                if files.len() >= MAX_LISTED_FILES { return files; }
+            }
+        }
+    }
+    files
+}
+
+fn sort_files(files: &mut [PathBuf], sort: NeighborSort) {
+    match sort {
+        NeighborSort::Natural => files.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy())),
+        NeighborSort::Modified => {
+            files.sort_by_cached_key(|p| fs::metadata(p).and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH));
+        }
+        NeighborSort::Size => files.sort_by_cached_key(|p| fs::metadata(p).map_or(0, |m| m.len())),
+    }
+}
+
+/// The files Prev/Next steps through from `current`, listed once and reused
+/// until the folder changes or the ordering options do.
+pub(crate) fn siblings<'a>(app: &'a mut FileViewerApp, current: &Path) -> &'a [PathBuf] {
+    let filter = ListFilter::for_file(current);
+    let (sort, recursive) = (app.neighbor_sort, app.neighbor_recursive);
+    let valid = app
+        .dir_listing
+        .as_ref()
+        .is_some_and(|l| l.is_valid_for(current, &filter, sort, recursive));
+    if !valid {
+        // A refreshed recursive listing keeps its root, so stepping into a subfolder doesn't strand Prev/Next there
+        let kept_root = app.dir_listing.as_ref().map(|l| &l.root).filter(|root| recursive && current.starts_with(root));
+        let root = kept_root.cloned().unwrap_or_else(|| current.parent().unwrap_or(Path::new(".")).to_path_buf());
+        let mut files = list_files(&root, &filter, recursive);
+        sort_files(&mut files, sort);
+        app.dir_listing = Some(DirListing {
+            root_mtime: dir_mtime(&root),
+            root,
+            filter,
+            sort,
+            recursive,
+            listed_at: Instant::now(),
+            files,
+        });
+    }
+    app.dir_listing.as_ref().map_or(&[], |l| &l.files)
+}
+
+/// Next or previous file after `current`, wrapping around at the ends.
+pub(crate) fn neighbor(app: &mut FileViewerApp, current: &Path, forward: bool) -> Option<PathBuf> {
+    let files = siblings(app, current);
+    if files.len() <= 1 { return None; }
+    let idx = files.iter().position(|p| p == current)?;
+    let next = if forward { (idx + 1) % files.len() } else { (idx + files.len() - 1) % files.len() };
+    files.get(next).cloned()
+}
@@ -0,0 +1,454 @@
+use eframe::egui;
+use egui::text::LayoutJob;
+use egui::{Color32, TextFormat};
+use regex::Regex;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::LazyLock;
+use crate::app::{Content, FileViewerApp};
+
+// Lines sampled when guessing whether a file without a .log extension is a log
+const SNIFF_LINES: usize = 200;
+// Levels are looked for near the start of a line only
+const LEVEL_SCAN_BYTES: usize = 160;
+const TIMESTAMP_COLOR: Color32 = Color32::from_rgb(86, 182, 194);
+const CURRENT_ERROR_BG: Color32 = Color32::from_rgba_premultiplied(120, 40, 40, 60);
+
+// ISO 8601 / RFC 3339, syslog ("Jan  2 15:04:05") or a bare time, optionally bracketed
+static TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\[?(?:\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?|[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}|\d{2}:\d{2}:\d{2}(?:[.,]\d+)?)\]?")
+        .expect("valid timestamp pattern")
+});
+// Upper-case level words, or `level=...` and JSON `"level": ...` style fields in any case
+static LEVEL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(FATAL|CRITICAL|CRIT|ERROR|ERR|WARNING|WARN|INFO|DEBUG|TRACE)\b|(?i)\b(?:level|lvl|severity)\x22?\s*[=:]\s*\x22?(fatal|critical|crit|error|err|warning|warn|info|debug|trace)\b")
+        .expect("valid level pattern")
+});
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub(crate) const ALL: [LogLevel; 5] = [LogLevel::Error, LogLevel::Warn, LogLevel::Info, LogLevel::Debug, LogLevel::Trace];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            LogLevel::Error => "Error",
+            LogLevel::Warn => "Warn",
+            LogLevel::Info => "Info",
+            LogLevel::Debug => "Debug",
+            LogLevel::Trace => "Trace",
+        }
+    }
+
+    pub(crate) fn color(self, text_color: Color32) -> Color32 {
+        match self {
+            LogLevel::Error => Color32::from_rgb(224, 108, 117),
+            LogLevel::Warn => Color32::from_rgb(229, 192, 123),
+            LogLevel::Info => text_color,
+            LogLevel::Debug => Color32::from_rgb(150, 150, 150),
+            LogLevel::Trace => Color32::from_rgb(110, 110, 110),
+        }
+    }
+
+    fn from_word(word: &str) -> Option<LogLevel> {
+        match word.to_ascii_uppercase().as_str() {
+            "FATAL" | "CRITICAL" | "CRIT" | "ERROR" | "ERR" => Some(LogLevel::Error),
+            "WARNING" | "WARN" => Some(LogLevel::Warn),
+            "INFO" => Some(LogLevel::Info),
+            "DEBUG" => Some(LogLevel::Debug),
+            "TRACE" => Some(LogLevel::Trace),
+            _ => None,
+        }
+    }
+}
+
+fn line_level(line: &str) -> Option<LogLevel> {
+    let mut end = line.len().min(LEVEL_SCAN_BYTES);
+    while !line.is_char_boundary(end) { end -= 1; }
+    let caps = LEVEL.captures(&line[..end])?;
+    LogLevel::from_word(caps.get(1).or_else(|| caps.get(2))?.as_str())
+}
+
+fn timestamp(line: &str) -> Option<Range<usize>> {
+    TIMESTAMP.find(line).map(|m| m.range())
+}
+
+/// True for `.log` files, or text whose first lines mostly start with a
+/// timestamp or carry a level.
+pub(crate) fn looks_like_log(path: &Path, text: &str) -> bool {
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    if ext == "log" { return true; }
+    let sample: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).take(SNIFF_LINES).collect();
+    if sample.len() < 3 { return false; }
+    let hits = sample.iter().filter(|l| timestamp(l).is_some() || line_level(l).is_some()).count();
+    hits * 10 >= sample.len() * 6
+}
+
+/// Per-line levels of a log, the filter over them and the rows left visible.
+pub(crate) struct LogView {
+    lines: Vec<Range<usize>>,
+    // Continuation lines (stack traces etc.) inherit the level above them
+    levels: Vec<Option<LogLevel>>,
+    // Lines that name their own level, where error jumps stop
+    leveled: Vec<bool>,
+    pub(crate) counts: [usize; 5],
+    pub(crate) first_time: Option<String>,
+    pub(crate) last_time: Option<String>,
+    pub(crate) shown_levels: [bool; 5],
+    pub(crate) pattern: String,
+    pub(crate) pattern_error: Option<String>,
+    regex: Option<Regex>,
+    pub(crate) visible: Vec<usize>,
+    // Line of the last error jumped to, highlighted until the next jump
+    pub(crate) current: Option<usize>,
+    scroll_to: Option<usize>,
+    /// Topmost line on screen, where error jumps start without a current one
+    pub(crate) first_visible_line: usize,
+    // Find match shown in the log view: (query, match index, its line, matches above that line)
+    search_hit: Option<(String, usize, usize, usize)>,
+}
+
+impl LogView {
+    pub(crate) fn new(text: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut levels = Vec::new();
+        let mut leveled = Vec::new();
+        let mut counts = [0; 5];
+        let (mut first_time, mut last_time) = (None, None);
+        let mut level = None;
+        for line in text.lines() {
+            let start = line.as_ptr() as usize - text.as_ptr() as usize;
+            lines.push(start..start + line.len());
+            let own = line_level(line);
+            if let Some(l) = own {
+                counts[l as usize] += 1;
+                level = Some(l);
+            }
+            levels.push(level);
+            leveled.push(own.is_some());
+            if let Some(ts) = timestamp(line) {
+                let ts = line[ts].trim_matches(['[', ']']).to_string();
+                if first_time.is_none() { first_time = Some(ts.clone()); }
+                last_time = Some(ts);
+            }
+        }
+        let mut view = Self {
+            lines,
+            levels,
+            leveled,
+            counts,
+            first_time,
+            last_time,
+            shown_levels: [true; 5],
+            pattern: String::new(),
+            pattern_error: None,
+            regex: None,
+            visible: Vec::new(),
+            current: None,
+            scroll_to: None,
+            first_visible_line: 0,
+            search_hit: None,
+        };
+        view.refilter(text);
+        view
+    }
+
+    pub(crate) fn is_filtered(&self) -> bool {
+        self.visible.len() != self.lines.len()
+    }
+
+    /// Whether `line` passes the level and pattern filters.
+    pub(crate) fn shows(&self, line: usize) -> bool {
+        self.visible.binary_search(&line).is_ok()
+    }
+
+    /// Text color of `line` for its level.
+    pub(crate) fn line_color(&self, line: usize, text_color: Color32) -> Color32 {
+        self.levels.get(line).copied().flatten().map_or(text_color, |l| l.color(text_color))
+    }
+
+    /// Color the timestamp of `line` (appended to `job` at `start`) and mark the
+    /// error last jumped to.
+    pub(crate) fn decorate(&self, job: &mut LayoutJob, start: usize, line_index: usize, line: &str) {
+        if let Some(ts) = timestamp(line) {
+            crate::highlight::restyle(job, start + ts.start..start + ts.end, |f| f.color = TIMESTAMP_COLOR);
+        }
+        if self.current == Some(line_index) {
+            let end = job.text.len();
+            crate::highlight::restyle(job, start..end, |f| f.background = CURRENT_ERROR_BG);
+        }
+    }
+
+    /// Lines that name an error level, for the overview ruler.
+    pub(crate) fn error_lines(&self) -> Vec<usize> {
+        (0..self.lines.len()).filter(|&i| self.leveled[i] && self.levels[i] == Some(LogLevel::Error)).collect()
+    }
+
+    /// The line an error jump asked to show, once.
+    pub(crate) fn take_scroll(&mut self) -> Option<usize> {
+        self.scroll_to.take()
+    }
+
+    /// Recompile the pattern and recompute the visible lines.
+    pub(crate) fn set_pattern(&mut self, text: &str) {
+        self.pattern_error = None;
+        self.regex = None;
+        if !self.pattern.is_empty() {
+            match regex::RegexBuilder::new(&self.pattern).case_insensitive(true).build() {
+                Ok(re) => self.regex = Some(re),
+                Err(e) => self.pattern_error = Some(e.to_string().lines().last().unwrap_or("invalid pattern").trim().to_string()),
+            }
+        }
+        self.refilter(text);
+    }
+
+    pub(crate) fn refilter(&mut self, text: &str) {
+        let all_levels = self.shown_levels.iter().all(|s| *s);
+        self.visible = (0..self.lines.len())
+            .filter(|&i| match self.levels[i] {
+                Some(level) => self.shown_levels[level as usize],
+                // Lines before the first leveled one only hide when levels are filtered
+                None => all_levels,
+            })
+            .filter(|&i| self.regex.as_ref().is_none_or(|re| re.is_match(&text[self.lines[i].clone()])))
+            .collect();
+    }
+
+    /// Scroll to the next (or previous) visible error after the last jump or the top of the view.
+    pub(crate) fn jump_to_error(&mut self, forward: bool) {
+        let errors: Vec<usize> = self.visible.iter().copied().filter(|&i| self.leveled[i] && self.levels[i] == Some(LogLevel::Error)).collect();
+        if errors.is_empty() { return; }
+        let from = self.current.unwrap_or(self.first_visible_line);
+        let next = if forward {
+            errors.iter().copied().find(|&i| i > from || (self.current.is_none() && i == from)).unwrap_or(errors[0])
+        } else {
+            errors.iter().rev().copied().find(|&i| i < from).unwrap_or(errors[errors.len() - 1])
+        };
+        self.current = Some(next);
+        self.scroll_to = Some(next);
+    }
+}
+
+/// Draw the visible lines of a big log, colored by level, with original line
+/// numbers. Smaller logs are drawn by the text view with the same coloring.
+pub(crate) fn show(ui: &mut egui::Ui, app: &mut FileViewerApp) {
+    let mut font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    font_id.size = (font_id.size * app.text_zoom).clamp(8.0, 48.0);
+    let text_color = ui.visuals().text_color();
+    let (Some(Content::Text(text)), Some(log)) = (&app.content, &mut app.log) else { return };
+    let row_height = ui.fonts(|f| f.row_height(&font_id));
+    let spacing = ui.spacing().item_spacing.y;
+    let digits = log.lines.len().max(1).to_string().len().max(4);
+
+    // Follow the find bar: locate the current match once per change and scroll to it
+    let query = app.search_query.as_str();
+    if query.is_empty() || app.search_count == 0 {
+        log.search_hit = None;
+    } else if log.search_hit.as_ref().is_none_or(|(q, current, _, _)| q != query || *current != app.search_current)
+        && let Some(matcher) = crate::search::Matcher::new(query)
+    {
+        let mut before = 0;
+        for (i, range) in log.lines.iter().enumerate() {
+            let count = matcher.find_iter(&text[range.clone()]).len();
+            if before + count > app.search_current {
+                log.search_hit = Some((query.to_string(), app.search_current, i, before));
+                log.scroll_to = Some(i);
+                break;
+            }
+            before += count;
+        }
+    }
+
+    let mut area = egui::ScrollArea::both().auto_shrink([false, false]);
+    if let Some(offset) = app.pending_scroll.take() {
+        area = area.scroll_offset(offset);
+    }
+    // Outline, bookmark and ruler jumps
+    if let Some(line) = app.goto_line.take() {
+        log.scroll_to = Some(line);
+    }
+    if let Some(line) = log.scroll_to.take() {
+        let row = log.visible.partition_point(|&i| i < line);
+        let view_height = ui.available_height();
+        area = area.vertical_scroll_offset((row as f32 * (row_height + spacing) - view_height / 2.0).max(0.0));
+    }
+    // Overview ruler over all lines, filtered or not
+    let mut text_rect = ui.available_rect_before_wrap();
+    let ruler_rect = app.show_overview.then(|| crate::overview::take_strip(ui, &mut text_rect, false));
+    if app.show_overview && app.overview.is_none() {
+        let git_marks = app.git_status.as_ref().map(|s| s.marks.as_slice());
+        app.overview = Some(crate::overview::Overview::new(text, git_marks, None, Some(log)));
+    }
+    let output = ui.scope_builder(egui::UiBuilder::new().max_rect(text_rect), |ui| egui::Frame::group(ui.style()).show(ui, |ui| {
+        area.show_rows(ui, row_height, log.visible.len(), |ui, rows| {
+            log.first_visible_line = log.visible.get(rows.start).copied().unwrap_or(0);
+            if let Some(overview) = &mut app.overview {
+                let last = rows.end.checked_sub(1).and_then(|r| log.visible.get(r)).map_or(log.first_visible_line, |&l| l);
+                overview.visible = Some(log.first_visible_line..last + 1);
+            }
+            for &i in &log.visible[rows] {
+                let line = &text[log.lines[i].clone()];
+                let mut job = LayoutJob::default();
+                if app.show_line_numbers {
+                    job.append(&format!("{:>digits$} ", i + 1), 0.0, TextFormat { font_id: font_id.clone(), color: Color32::GRAY, ..Default::default() });
+                }
+                let start = job.text.len();
+                // Matches are numbered from the current one's line, the only one it can be on
+                let (current, mut counter) = match &log.search_hit {
+                    Some((_, current, hit_line, before)) if *hit_line == i => (*current, *before),
+                    _ => (usize::MAX, 0),
+                };
+                crate::highlight::append_with_search(&mut job, line, font_id.clone(), log.line_color(i, text_color), query, current, &mut counter);
+                log.decorate(&mut job, start, i, line);
+                job.wrap.max_width = f32::INFINITY;
+                ui.label(job);
+            }
+        })
+    })).inner;
+    app.scroll_offset = output.inner.state.offset;
+    if let Some(rect) = ruler_rect
+        && let Some(overview) = &mut app.overview
+        && let Some(line) = crate::overview::show(ui, rect, overview, text, query, &app.bookmarks, false)
+    {
+        // Filtered-out lines scroll to the next one shown
+        app.goto_line = Some(line);
+        ui.ctx().request_repaint();
+    }
+}
+
+/// Level toggles with counts, the pattern filter and error navigation.
+pub(crate) fn log_bar(ui: &mut egui::Ui, app: &mut FileViewerApp) {
+    let text_color = ui.visuals().text_color();
+    let (Some(Content::Text(text)), Some(log)) = (&app.content, &mut app.log) else { return };
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Levels:");
+        let mut changed = false;
+        for level in LogLevel::ALL {
+            let label = egui::RichText::new(format!("{} {}", level.label(), log.counts[level as usize])).color(level.color(text_color));
+            changed |= ui.toggle_value(&mut log.shown_levels[level as usize], label).changed();
+        }
+        ui.separator();
+        ui.label("Filter:");
+        let resp = ui.add(egui::TextEdit::singleline(&mut log.pattern).hint_text("regex").desired_width(200.0));
+        if resp.changed() {
+            log.set_pattern(text);
+        } else if changed {
+            log.refilter(text);
+        }
+        if let Some(err) = &log.pattern_error {
+            ui.colored_label(Color32::RED, err);
+        }
+        ui.separator();
+        if ui.button("Prev Error").on_hover_text("Shift+F8").clicked() { log.jump_to_error(false); }
+        if ui.button("Next Error").on_hover_text("F8").clicked() { log.jump_to_error(true); }
+        if log.is_filtered() {
+            ui.separator();
+            ui.label(format!("{} of {} lines", log.visible.len(), log.lines.len()));
+        }
+        if let (Some(first), Some(last)) = (&log.first_time, &log.last_time) {
+            ui.separator();
+            ui.label(format!("{}  →  {}", first, last)).on_hover_text("First and last timestamp");
+        }
+    });
+}
+
+/// Per-level counts for the status bar, most severe first.
+pub(crate) fn level_counts(ui: &mut egui::Ui, log: &LogView) {
+    let text_color = ui.visuals().text_color();
+    for level in LogLevel::ALL.into_iter().rev() {
+        let count = log.counts[level as usize];
+        if count > 0 {
+            ui.label(egui::RichText::new(format!("{}: {}", level.label(), count)).color(level.color(text_color)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_words_and_fields() {
+        assert_eq!(line_level("2024-05-01 12:00:00 ERROR disk full"), Some(LogLevel::Error));
+        assert_eq!(line_level("12:00:00 ERR connection reset"), Some(LogLevel::Error));
+        assert_eq!(line_level("ts=2024-05-01 level=warn msg=slow"), Some(LogLevel::Warn));
+        assert_eq!(line_level(r#"{"severity": "Debug", "msg": "x"}"#), Some(LogLevel::Debug));
+        // Bare level words count only in upper case and as whole words
+        assert_eq!(line_level("info: starting"), None);
+        assert_eq!(line_level("ERRORS were counted"), None);
+    }
+
+    #[test]
+    fn level_is_looked_for_near_the_start_only() {
+        let line = format!("{} ERROR", "x".repeat(LEVEL_SCAN_BYTES));
+        assert_eq!(line_level(&line), None);
+    }
+
+    #[test]
+    fn continuation_lines_inherit_the_level_above() {
+        let text = "10:00:00 INFO start\n10:00:01 ERROR boom\n    at main.rs:3\n10:00:02 WARN slow\n";
+        let log = LogView::new(text);
+        assert_eq!(log.levels, vec![Some(LogLevel::Info), Some(LogLevel::Error), Some(LogLevel::Error), Some(LogLevel::Warn)]);
+        assert_eq!(log.counts[LogLevel::Error as usize], 1);
+        // Only the line naming the level is an error stop
+        assert_eq!(log.error_lines(), vec![1]);
+        assert_eq!(log.first_time.as_deref(), Some("10:00:00"));
+        assert_eq!(log.last_time.as_deref(), Some("10:00:02"));
+    }
+
+    #[test]
+    fn sniffs_logs_at_sixty_percent() {
+        let lines = |stamped: usize, plain: usize| {
+            let mut text = "2024-05-01T10:00:00Z started\n".repeat(stamped);
+            text.push_str(&"plain text line\n".repeat(plain));
+            text
+        };
+        assert!(looks_like_log(Path::new("notes.txt"), &lines(6, 4)));
+        assert!(!looks_like_log(Path::new("notes.txt"), &lines(5, 5)));
+        // Too short to tell, unless the extension says so
+        assert!(!looks_like_log(Path::new("notes.txt"), &lines(2, 0)));
+        assert!(looks_like_log(Path::new("app.LOG"), "plain text\n"));
+    }
+
+    #[test]
+    fn filters_by_level_and_pattern() {
+        let text = "preamble\n10:00:00 INFO db up\n10:00:01 DEBUG db query\n10:00:02 ERROR db down\n    retrying db\n10:00:03 INFO web up\n";
+        let mut log = LogView::new(text);
+        assert!(!log.is_filtered());
+        log.pattern = "DB".to_string();
+        log.set_pattern(text);
+        assert_eq!(log.visible, vec![1, 2, 3, 4]);
+        log.shown_levels[LogLevel::Debug as usize] = false;
+        log.refilter(text);
+        // The preamble has no level, so it hides once levels are filtered
+        assert_eq!(log.visible, vec![1, 3, 4]);
+        assert!(log.shows(4) && !log.shows(2));
+        log.pattern = "(".to_string();
+        log.set_pattern(text);
+        assert!(log.pattern_error.is_some());
+        assert_eq!(log.visible, vec![1, 3, 4, 5]);
+    }
+
+    #[test]
+    fn error_jumps_wrap_and_skip_continuations() {
+        let text = "10:00:00 ERROR a\n  trace\n10:00:01 INFO b\n10:00:02 ERROR c\n";
+        let mut log = LogView::new(text);
+        log.jump_to_error(true);
+        assert_eq!(log.current, Some(0));
+        log.jump_to_error(true);
+        assert_eq!(log.current, Some(3));
+        log.jump_to_error(true);
+        assert_eq!(log.current, Some(0));
+        log.jump_to_error(false);
+        assert_eq!(log.current, Some(3));
+        assert_eq!(log.take_scroll(), Some(3));
+        assert_eq!(log.take_scroll(), None);
+    }
+}
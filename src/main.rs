@@ -1,27 +1,59 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
 mod app;
+mod blame;
+mod brackets;
+mod cli;
+mod compare;
+mod diff;
+mod diff_view;
+mod folder_search;
+mod folding;
+mod gallery;
+mod git;
 mod highlight;
+mod image_view;
+mod inspector;
 mod search;
+mod session;
 mod io;
+mod line_filter;
+mod listing;
+mod log_view;
+mod metadata;
+mod outline;
+mod overview;
 mod settings;
+mod slideshow;
+mod tiles;
+mod transform;
 mod ui;
+mod view_state;
 
 use app::FileViewerApp;
 use eframe::egui;
 
 fn main() -> Result<(), eframe::Error> {
+    let args = cli::parse_args();
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size([1000.0, 700.0])
+        .with_resizable(true)
+        .with_title("Gemini File Viewer 2.0");
+    // Restore the previous window geometry unless the session is disabled
+    if !args.no_session
+        && let Some(saved) = settings::load_settings_from_disk()
+        && saved.restore_session
+    {
+        viewport = saved.session.window.apply(viewport);
+    }
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([1000.0, 700.0])
-            .with_resizable(true)
-            .with_title("Gemini File Viewer 2.0"),
+        viewport,
         ..Default::default()
     };
 
     eframe::run_native(
         "Gemini File Viewer 2.0",
         options,
-        Box::new(|cc| Ok(Box::new(FileViewerApp::new(cc))))
+        Box::new(move |cc| Ok(Box::new(FileViewerApp::new(cc, &args))))
     )
 }
@@ -0,0 +1,205 @@
+use image::ImageDecoder;
+use std::fs;
+use std::io::BufReader;
+use std::path::Path;
+
+// Long values (maker notes, embedded blobs) are cut to keep the panel readable
+const MAX_VALUE_CHARS: usize = 120;
+
+/// Image metadata grouped into titled sections of key/value rows.
+#[derive(Default)]
+pub(crate) struct ImageMetadata {
+    pub(crate) sections: Vec<MetadataSection>,
+}
+
+pub(crate) struct MetadataSection {
+    pub(crate) title: String,
+    pub(crate) rows: Vec<(String, String)>,
+}
+
+impl MetadataSection {
+    fn new(title: &str) -> Self {
+        Self { title: title.to_string(), rows: Vec::new() }
+    }
+
+    fn push(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        let mut value: String = value.into();
+        if value.chars().count() > MAX_VALUE_CHARS {
+            value = value.chars().take(MAX_VALUE_CHARS).collect::<String>() + "…";
+        }
+        self.rows.push((key.into(), value));
+    }
+}
+
+/// Read format details, EXIF, ICC and PNG text chunks. Missing pieces are skipped.
+pub(crate) fn read_metadata(path: &Path) -> ImageMetadata {
+    let mut meta = ImageMetadata::default();
+    let mut format = MetadataSection::new("Format");
+    let mut exif_raw = None;
+    let mut icc_raw = None;
+
+    let info = match crate::io::detect_content_kind(path) {
+        crate::io::ContentKind::Image(info) => Some(info),
+        crate::io::ContentKind::Text => None,
+    };
+    match info {
+        Some(info) if info.codec == crate::io::ImageCodec::Svg => {
+            format.push("Format", "SVG");
+            format.push("Rendering", "Vector, rasterized at the current zoom");
+        }
+        Some(info) => read_decoder_details(path, info, &mut format, &mut exif_raw, &mut icc_raw),
+        None => format.push("Error", "Not a recognized image"),
+    }
+    if let Ok(md) = fs::metadata(path) {
+        format.push("File size", format!("{} bytes", md.len()));
+    }
+    meta.sections.push(format);
+
+    if info.is_some_and(|i| i.codec == crate::io::ImageCodec::Raster(image::ImageFormat::Png))
+        && let Some(section) = png_section(path)
+    {
+        meta.sections.push(section);
+    }
+    if let Some(raw) = exif_raw
+        && let Some(section) = exif_section(raw)
+    {
+        meta.sections.push(section);
+    }
+    if let Some(icc) = icc_raw {
+        meta.sections.push(icc_section(&icc));
+    }
+    meta
+}
+
+fn read_decoder_details(path: &Path, info: &crate::io::FormatInfo, format: &mut MetadataSection, exif_raw: &mut Option<Vec<u8>>, icc_raw: &mut Option<Vec<u8>>) {
+    match crate::io::image_reader(path, info) {
+        Ok(reader) => {
+            format.push("Format", info.name);
+            match reader.into_decoder() {
+                Ok(mut decoder) => {
+                    let (w, h) = decoder.dimensions();
+                    format.push("Dimensions", format!("{} x {}", w, h));
+                    let color = decoder.original_color_type();
+                    format.push("Color type", format!("{:?}", color));
+                    let channels = color.channel_count().max(1);
+                    format.push("Channels", channels.to_string());
+                    format.push("Bit depth", format!("{} bits/channel", color.bits_per_pixel() / channels as u16));
+                    format.push("Decoded as", format!("{:?}", decoder.color_type()));
+                    *exif_raw = decoder.exif_metadata().ok().flatten();
+                    *icc_raw = decoder.icc_profile().ok().flatten();
+                }
+                Err(e) => format.push("Error", e.to_string()),
+            }
+        }
+        Err(e) => format.push("Error", e),
+    }
+}
+
+fn png_section(path: &Path) -> Option<MetadataSection> {
+    let file = fs::File::open(path).ok()?;
+    let reader = png::Decoder::new(BufReader::new(file)).read_info().ok()?;
+    let info = reader.info();
+    let mut section = MetadataSection::new("PNG");
+    section.push("Color type", format!("{:?}", info.color_type));
+    section.push("Bit depth", format!("{}", info.bit_depth as u8));
+    section.push("Interlaced", if info.interlaced { "yes" } else { "no" });
+    if let Some(gamma) = info.source_gamma {
+        section.push("Gamma", format!("{:.5}", gamma.into_value()));
+    }
+    if let Some(anim) = info.animation_control {
+        section.push("Animation", format!("{} frames", anim.num_frames));
+    }
+    for chunk in &info.uncompressed_latin1_text {
+        section.push(format!("tEXt {}", chunk.keyword), chunk.text.clone());
+    }
+    for chunk in &info.compressed_latin1_text {
+        section.push(format!("zTXt {}", chunk.keyword), chunk.get_text().unwrap_or_default());
+    }
+    for chunk in &info.utf8_text {
+        section.push(format!("iTXt {}", chunk.keyword), chunk.get_text().unwrap_or_default());
+    }
+    Some(section)
+}
+
+fn exif_section(raw: Vec<u8>) -> Option<MetadataSection> {
+    let exif = exif::Reader::new().read_raw(raw).ok()?;
+    let mut section = MetadataSection::new("EXIF");
+    if let Some((lat, lon)) = gps_position(&exif) {
+        section.push("GPS position", format!("{:.6}, {:.6}", lat, lon));
+    }
+    for field in exif.fields().filter(|f| f.ifd_num == exif::In::PRIMARY) {
+        if field.tag == exif::Tag::MakerNote { continue; }
+        let value = field.display_value().with_unit(&exif).to_string();
+        section.push(field.tag.to_string(), value.trim_matches('"').to_string());
+    }
+    Some(section)
+}
+
+// Latitude/longitude in signed decimal degrees
+fn gps_position(exif: &exif::Exif) -> Option<(f64, f64)> {
+    let coord = |tag: exif::Tag, ref_tag: exif::Tag, negative: &str| -> Option<f64> {
+        let field = exif.get_field(tag, exif::In::PRIMARY)?;
+        let exif::Value::Rational(parts) = &field.value else { return None };
+        let deg = parts.first()?.to_f64() + parts.get(1).map_or(0.0, |r| r.to_f64() / 60.0) + parts.get(2).map_or(0.0, |r| r.to_f64() / 3600.0);
+        let sign = exif
+            .get_field(ref_tag, exif::In::PRIMARY)
+            .map(|f| f.display_value().to_string())
+            .filter(|r| r.contains(negative))
+            .map_or(1.0, |_| -1.0);
+        Some(deg * sign)
+    };
+    Some((
+        coord(exif::Tag::GPSLatitude, exif::Tag::GPSLatitudeRef, "S")?,
+        coord(exif::Tag::GPSLongitude, exif::Tag::GPSLongitudeRef, "W")?,
+    ))
+}
+
+fn icc_section(icc: &[u8]) -> MetadataSection {
+    let mut section = MetadataSection::new("ICC Profile");
+    if let Some(name) = icc_description(icc) {
+        section.push("Description", name);
+    }
+    let ascii = |range: std::ops::Range<usize>| {
+        icc.get(range).map(|b| String::from_utf8_lossy(b).trim().to_string())
+    };
+    if let Some(class) = ascii(12..16) { section.push("Device class", class); }
+    if let Some(space) = ascii(16..20) { section.push("Color space", space); }
+    if let Some(&[major, minor, ..]) = icc.get(8..12) {
+        section.push("Version", format!("{}.{}", major, minor >> 4));
+    }
+    section.push("Size", format!("{} bytes", icc.len()));
+    section
+}
+
+// Profile name from the 'desc' tag: ICC v2 'desc' (ASCII) or v4 'mluc' (UTF-16BE)
+fn icc_description(icc: &[u8]) -> Option<String> {
+    let be32 = |at: usize| -> Option<usize> {
+        Some(u32::from_be_bytes(icc.get(at..at + 4)?.try_into().ok()?) as usize)
+    };
+    let count = be32(128)?;
+    for i in 0..count.min(256) {
+        let entry = 132 + i * 12;
+        if icc.get(entry..entry + 4)? != b"desc" { continue; }
+        let offset = be32(entry + 4)?;
+        let tag = icc.get(offset..offset + be32(entry + 8)?)?;
+        return match tag.get(0..4)? {
+            b"desc" => {
+                let len = u32::from_be_bytes(tag.get(8..12)?.try_into().ok()?) as usize;
+                let text = tag.get(12..12 + len)?;
+                Some(String::from_utf8_lossy(text).trim_end_matches('\0').to_string())
+            }
+            b"mluc" => {
+                let len = u32::from_be_bytes(tag.get(20..24)?.try_into().ok()?) as usize;
+                let start = u32::from_be_bytes(tag.get(24..28)?.try_into().ok()?) as usize;
+                let units: Vec<u16> = tag
+                    .get(start..start + len)?
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                Some(String::from_utf16_lossy(&units).trim_end_matches('\0').to_string())
+            }
+            _ => None,
+        };
+    }
+    None
+}
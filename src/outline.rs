@@ -0,0 +1,315 @@
+use eframe::egui;
+use egui::{Color32, RichText};
+use crate::app::{Content, FileViewerApp};
+use crate::highlight::tokens;
+
+const KIND_COLOR: Color32 = Color32::from_rgb(97, 175, 239);
+// Words that may precede a defining keyword
+const MODIFIERS: &[&str] = &[
+    "pub", "crate", "super", "in", "(", ")", "async", "unsafe", "const", "extern", "export", "default", "declare",
+    "abstract", "public", "private", "protected", "internal", "static", "final", "sealed", "open", "data", "override",
+    "inline", "virtual", "partial", "readonly", "typedef",
+];
+
+/// A definition or heading listed in the outline.
+pub(crate) struct Symbol {
+    /// Defining keyword ("fn", "class", ...); empty for headings and keys
+    pub(crate) kind: &'static str,
+    pub(crate) name: String,
+    pub(crate) line: usize,
+    pub(crate) depth: usize,
+}
+
+/// Definitions of a source file, headings of a Markdown file or top-level
+/// keys (or array elements) of a JSON file, in file order.
+pub(crate) struct Outline {
+    pub(crate) symbols: Vec<Symbol>,
+    // Entry last scrolled into view, so the list only follows the text when it changes
+    synced: Option<usize>,
+}
+
+impl Outline {
+    /// None for file types without an outline.
+    pub(crate) fn compute(text: &str, ext: &str) -> Option<Self> {
+        let symbols = match ext {
+            "md" | "markdown" => headings(text),
+            "json" => json_keys(text),
+            _ => definitions(text, keywords(ext)?),
+        };
+        Some(Self { symbols, synced: None })
+    }
+
+    /// The last entry at or above `line`.
+    fn current(&self, line: usize) -> Option<usize> {
+        self.symbols.partition_point(|s| s.line <= line).checked_sub(1)
+    }
+}
+
+// Keywords introducing a definition named by the following word. "impl" is
+// handled separately since its name is the rest of the header.
+fn keywords(ext: &str) -> Option<&'static [&'static str]> {
+    Some(match ext {
+        "rs" => &["fn", "struct", "enum", "trait", "impl", "mod", "type", "union", "macro_rules"],
+        "py" => &["def", "class"],
+        "js" | "ts" => &["function", "class", "interface", "enum", "type"],
+        "go" => &["func", "type"],
+        "java" | "cs" | "kt" => &["class", "interface", "enum", "record", "object", "fun"],
+        "c" | "cpp" | "h" | "hpp" => &["struct", "class", "enum", "union", "namespace"],
+        "rb" => &["def", "class", "module"],
+        _ => return None,
+    })
+}
+
+fn definitions(text: &str, keywords: &[&'static str]) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    // Indents of the enclosing definitions, to nest methods under their types
+    let mut open: Vec<usize> = Vec::new();
+    let mut in_block_comment = false;
+    for (i, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if in_block_comment {
+            in_block_comment = !trimmed.contains("*/");
+            continue;
+        }
+        if trimmed.starts_with("/*") {
+            in_block_comment = !trimmed.contains("*/");
+            continue;
+        }
+        if trimmed.starts_with("//") || trimmed.starts_with('#') || trimmed.starts_with('*') {
+            continue;
+        }
+        let words: Vec<&str> = tokens(trimmed).filter(|t| !t.trim().is_empty()).collect();
+        let Some((kind, name)) = definition(&words, keywords, trimmed) else { continue };
+        let indent = line.len() - trimmed.len();
+        while open.last().is_some_and(|&o| o >= indent) {
+            open.pop();
+        }
+        symbols.push(Symbol { kind, name, line: i, depth: open.len() });
+        open.push(indent);
+    }
+    symbols
+}
+
+// The first keyword on the line followed by a name, skipping modifiers such as
+// "pub", "async" or "export" before it
+fn definition(words: &[&str], keywords: &[&'static str], line: &str) -> Option<(&'static str, String)> {
+    let at = words.iter().position(|w| keywords.contains(w))?;
+    // Keywords mid-expression ("let x = fn ...") or in prose are not definitions
+    if !words[..at].iter().all(|w| MODIFIERS.contains(w)) {
+        return None;
+    }
+    let kind = keywords.iter().find(|k| **k == words[at]).copied()?;
+    if kind == "impl" {
+        let header = line.split_once("impl")?.1;
+        let header = header.split(['{', ';']).next()?.split(" where ").next()?;
+        return Some((kind, skip_generics(header).trim().to_string())).filter(|(_, name)| !name.is_empty());
+    }
+    let mut rest = &words[at + 1..];
+    if kind == "macro_rules" {
+        rest = rest.strip_prefix(&["!"]).unwrap_or(rest);
+    }
+    // Go methods: "func (r *T) Name(...)"
+    if kind == "func" && rest.first() == Some(&"(") {
+        let close = rest.iter().position(|w| *w == ")")?;
+        rest = &rest[close + 1..];
+    }
+    let name = rest.first().filter(|w| w.starts_with(|c: char| c.is_alphabetic() || c == '_'))?;
+    Some((kind, name.to_string()))
+}
+
+// The rest of an impl header after its own "<...>" parameters
+fn skip_generics(header: &str) -> &str {
+    if !header.starts_with('<') {
+        return header;
+    }
+    let mut depth = 0;
+    for (i, c) in header.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return &header[i + 1..];
+        }
+    }
+    ""
+}
+
+fn headings(text: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let mut in_fence = false;
+    let mut previous = "";
+    for (i, line) in text.lines().enumerate() {
+        let above = std::mem::replace(&mut previous, line);
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        // Setext headings: a paragraph line underlined with === or ---
+        let underline = line.trim_end();
+        let paragraph = !above.trim().is_empty() && !above.trim_start().starts_with(['#', '-', '*', '+', '>', '|']);
+        if !in_fence && paragraph && underline.len() >= 2 {
+            for (marker, depth) in [('=', 0), ('-', 1)] {
+                if underline.chars().all(|c| c == marker) {
+                    symbols.push(Symbol { kind: "", name: above.trim().to_string(), line: i - 1, depth });
+                }
+            }
+        }
+        let level = line.chars().take_while(|c| *c == '#').count();
+        if in_fence || !(1..=6).contains(&level) || !line[level..].starts_with(' ') {
+            continue;
+        }
+        let name = line[level..].trim().trim_end_matches('#').trim_end();
+        symbols.push(Symbol { kind: "", name: name.to_string(), line: i, depth: level - 1 });
+    }
+    symbols
+}
+
+// Keys of the top-level object: strings directly inside it followed by ':'.
+// A top-level array lists its elements as [0], [1], ... instead
+fn json_keys(text: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let mut depth = 0usize;
+    let mut line = 0;
+    let mut root_is_array = None;
+    // Inside the top-level array, right after its '[' or a ','
+    let mut element_next = false;
+    let mut chars = text.char_indices();
+    while let Some((pos, c)) = chars.next() {
+        if c.is_whitespace() {
+            if c == '\n' { line += 1; }
+            continue;
+        }
+        let root_is_array = *root_is_array.get_or_insert(c == '[');
+        if root_is_array && depth == 1 && std::mem::take(&mut element_next) && c != ']' {
+            let index = symbols.len();
+            symbols.push(Symbol { kind: "", name: format!("[{}]", index), line, depth: 0 });
+        }
+        match c {
+            '{' | '[' => {
+                depth += 1;
+                element_next = depth == 1;
+            }
+            '}' | ']' => depth = depth.saturating_sub(1),
+            ',' => element_next = depth == 1,
+            '"' => {
+                let mut end = text.len();
+                while let Some((p, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => {
+                            end = p;
+                            break;
+                        }
+                        '\n' => line += 1,
+                        _ => {}
+                    }
+                }
+                if !root_is_array && depth == 1 && text.get(end + 1..).is_some_and(|rest| rest.trim_start().starts_with(':')) {
+                    symbols.push(Symbol { kind: "", name: text[pos + 1..end].to_string(), line, depth: 0 });
+                }
+            }
+            _ => {}
+        }
+    }
+    symbols
+}
+
+/// Whether the outline panel has something to show this frame.
+pub(crate) fn is_visible(app: &FileViewerApp) -> bool {
+    app.show_outline && app.outline.is_some() && app.text_diff.is_none() && app.gallery.is_none() && matches!(app.content, Some(Content::Text(_)))
+}
+
+/// List the symbols, marking the one the middle of the text view is in;
+/// clicking one scrolls the text to it.
+pub(crate) fn show(ui: &mut egui::Ui, app: &mut FileViewerApp) {
+    let view_line = app.text_view_line;
+    let Some(outline) = &mut app.outline else { return };
+    ui.heading("Outline");
+    ui.separator();
+    if outline.symbols.is_empty() {
+        ui.label("No symbols found.");
+        return;
+    }
+    let current = outline.current(view_line);
+    let follow = current != outline.synced;
+    outline.synced = current;
+    let mut clicked = None;
+    egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        for (idx, symbol) in outline.symbols.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add_space(symbol.depth as f32 * 12.0);
+                if !symbol.kind.is_empty() {
+                    ui.label(RichText::new(symbol.kind).monospace().color(KIND_COLOR));
+                }
+                let resp = ui.selectable_label(current == Some(idx), &symbol.name);
+                if follow && current == Some(idx) {
+                    resp.scroll_to_me(Some(egui::Align::Center));
+                }
+                if resp.on_hover_text(format!("Line {}", symbol.line + 1)).clicked() {
+                    clicked = Some(symbol.line);
+                }
+            });
+        }
+    });
+    if let Some(line) = clicked {
+        app.filter_lines = false;
+        app.goto_line = Some(line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(symbols: &[Symbol]) -> Vec<(&str, &str, usize, usize)> {
+        symbols.iter().map(|s| (s.kind, s.name.as_str(), s.line, s.depth)).collect()
+    }
+
+    fn outline(text: &str, ext: &str) -> Vec<Symbol> {
+        Outline::compute(text, ext).expect("known file type").symbols
+    }
+
+    #[test]
+    fn rust_definitions_after_modifiers() {
+        let text = "pub(crate) async fn run() {}\nimpl<T: Clone> Foo for Bar<T> where T: Send {\n    pub(super) unsafe fn get(&self) {}\n}\nlet f = fn_ptr;\n// fn commented()\n";
+        assert_eq!(names(&outline(text, "rs")), vec![
+            ("fn", "run", 0, 0),
+            ("impl", "Foo for Bar<T>", 1, 0),
+            ("fn", "get", 2, 1),
+        ]);
+    }
+
+    #[test]
+    fn go_method_receivers_are_skipped() {
+        let text = "type Server struct {}\nfunc (s *Server) Start(ctx context.Context) error {\n}\nfunc main() {}\n";
+        assert_eq!(names(&outline(text, "go")), vec![("type", "Server", 0, 0), ("func", "Start", 1, 0), ("func", "main", 3, 0)]);
+    }
+
+    #[test]
+    fn markdown_headings_skip_code_fences() {
+        let text = "Title\n=====\n\nSection\n-------\n```sh\n# not a heading\n```\n## Usage ##\n- item\n---\n";
+        assert_eq!(names(&outline(text, "md")), vec![("", "Title", 0, 0), ("", "Section", 3, 1), ("", "Usage", 8, 1)]);
+    }
+
+    #[test]
+    fn json_top_level_keys_only() {
+        let text = "{\n  \"name\": \"x\",\n  \"deps\": {\"inner\": 1},\n  \"list\": [\"a:\", {\"k\": 2}]\n}\n";
+        assert_eq!(names(&outline(text, "json")), vec![("", "name", 1, 0), ("", "deps", 2, 0), ("", "list", 3, 0)]);
+    }
+
+    #[test]
+    fn json_top_level_array_lists_elements() {
+        let text = "[\n  {\"id\": 1},\n  {\"id\": 2, \"tags\": [1, 2]},\n  \"three\"\n]\n";
+        assert_eq!(names(&outline(text, "json")), vec![("", "[0]", 1, 0), ("", "[1]", 2, 0), ("", "[2]", 3, 0)]);
+        assert!(outline("[]", "json").is_empty());
+    }
+
+    #[test]
+    fn unknown_types_have_no_outline() {
+        assert!(Outline::compute("fn main() {}", "txt").is_none());
+    }
+}
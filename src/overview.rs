@@ -0,0 +1,131 @@
+use eframe::egui;
+use egui::{Color32, Rect, Sense};
+use std::collections::BTreeSet;
+use std::ops::Range;
+use crate::brackets::Brackets;
+use crate::git::LineChange;
+use crate::log_view::LogView;
+use crate::search::Matcher;
+
+const RULER_WIDTH: f32 = 14.0;
+const MINIMAP_WIDTH: f32 = 90.0;
+const HIT_COLOR: Color32 = Color32::from_rgb(230, 200, 60);
+pub(crate) const BOOKMARK_COLOR: Color32 = Color32::from_rgb(80, 160, 240);
+// Widest line drawn in the minimap, in columns
+const MINIMAP_COLUMNS: f32 = 120.0;
+
+/// Marks of the open text for the overview ruler beside the text view, built
+/// once per file; search hits are refreshed when the query changes.
+pub(crate) struct Overview {
+    line_count: usize,
+    // Indent and trimmed length of each line, in columns, for the minimap
+    shapes: Vec<(u16, u16)>,
+    changes: Vec<(usize, Color32)>,
+    errors: Vec<usize>,
+    // Lines with a find match and the query they were found for
+    hits: (String, Vec<usize>),
+    /// Lines shown in the text view, set while it renders
+    pub(crate) visible: Option<Range<usize>>,
+}
+
+impl Overview {
+    pub(crate) fn new(text: &str, git_marks: Option<&[Option<LineChange>]>, brackets: Option<&Brackets>, log: Option<&LogView>) -> Self {
+        let shapes: Vec<(u16, u16)> = text
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                let indent = line[..line.len() - trimmed.len()].chars().map(|c| if c == '\t' { 4 } else { 1 }).sum::<usize>();
+                (indent.min(u16::MAX as usize) as u16, trimmed.trim_end().chars().count().min(u16::MAX as usize) as u16)
+            })
+            .collect();
+        let changes = git_marks
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, mark)| mark.map(|m| (i, crate::git::gutter_marker(Some(m)).1)))
+            .collect();
+        // Unbalanced brackets and log lines at error level
+        let mut errors: Vec<usize> = brackets.map_or_else(Vec::new, |b| (0..shapes.len()).filter(|&i| b.on_line(i).iter().any(|(_, partner)| partner.is_none())).collect());
+        if let Some(log) = log {
+            errors.extend(log.error_lines());
+            errors.sort_unstable();
+            errors.dedup();
+        }
+        Self { line_count: shapes.len(), shapes, changes, errors, hits: (String::new(), Vec::new()), visible: None }
+    }
+
+    fn refresh_hits(&mut self, text: &str, query: &str) {
+        if self.hits.0 == query {
+            return;
+        }
+        let lines = Matcher::new(query).map_or_else(Vec::new, |m| text.lines().enumerate().filter(|(_, l)| m.is_match(l)).map(|(i, _)| i).collect());
+        self.hits = (query.to_string(), lines);
+    }
+}
+
+/// Take the ruler's strip, wider with the minimap, off the right of `rect`.
+pub(crate) fn take_strip(ui: &egui::Ui, rect: &mut Rect, minimap: bool) -> Rect {
+    let width = if minimap { MINIMAP_WIDTH } else { RULER_WIDTH };
+    let strip = Rect::from_min_max(egui::pos2(rect.right() - width, rect.top()), rect.max);
+    rect.max.x = strip.left() - ui.spacing().item_spacing.x;
+    strip
+}
+
+/// Draw the ruler (and minimap) in `rect`. Returns the line clicked or
+/// dragged to, for the text view to scroll to.
+pub(crate) fn show(ui: &mut egui::Ui, rect: Rect, overview: &mut Overview, text: &str, query: &str, bookmarks: &BTreeSet<usize>, minimap: bool) -> Option<usize> {
+    overview.refresh_hits(text, query);
+    let resp = ui.allocate_rect(rect, Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    let total = overview.line_count.max(1) as f32;
+    let y_of = |line: usize| rect.top() + line as f32 / total * rect.height();
+    // At least a couple of pixels per mark so single lines stay visible in long files
+    let mark_height = (rect.height() / total).max(2.0);
+
+    if minimap {
+        let scale = rect.width() / MINIMAP_COLUMNS;
+        let color = ui.visuals().text_color().gamma_multiply(0.35);
+        // One sampled line per pixel row when the file is taller than the map
+        let rows = (overview.line_count as f32).min(rect.height()).max(1.0) as usize;
+        for row in 0..rows {
+            let line = row * overview.line_count / rows;
+            let Some(&(indent, len)) = overview.shapes.get(line) else { continue };
+            if len == 0 { continue; }
+            let y = y_of(line);
+            let x = rect.left() + indent as f32 * scale;
+            let width = (len as f32 * scale).min(rect.right() - x);
+            painter.rect_filled(Rect::from_min_size(egui::pos2(x, y), egui::vec2(width, (mark_height - 1.0).max(1.0))), 0.0, color);
+        }
+    }
+
+    if let Some(visible) = &overview.visible {
+        let band = Rect::from_x_y_ranges(rect.x_range(), y_of(visible.start)..=y_of(visible.end).max(y_of(visible.start) + 4.0));
+        painter.rect_filled(band, 2.0, ui.visuals().text_color().gamma_multiply(0.12));
+    }
+
+    // Git changes on the left edge, find matches in the middle, errors on the right
+    let third = rect.width() / 3.0;
+    let lane = |index: f32, line: usize| Rect::from_min_size(egui::pos2(rect.left() + index * third, y_of(line)), egui::vec2(third, mark_height));
+    for &(line, color) in &overview.changes {
+        painter.rect_filled(lane(0.0, line), 0.0, color);
+    }
+    for &line in &overview.hits.1 {
+        painter.rect_filled(lane(1.0, line), 0.0, HIT_COLOR);
+    }
+    for &line in &overview.errors {
+        painter.rect_filled(lane(2.0, line), 0.0, crate::app::UNBALANCED_BRACKET_COLOR);
+    }
+    // Bookmarks across the whole width, over the other marks
+    for &line in bookmarks {
+        painter.rect_filled(Rect::from_min_size(egui::pos2(rect.left(), y_of(line)), egui::vec2(rect.width(), mark_height)), 0.0, BOOKMARK_COLOR);
+    }
+
+    let line_at = |y: f32| (((y - rect.top()) / rect.height()).clamp(0.0, 1.0) * total) as usize;
+    if let Some(pos) = resp.hover_pos() {
+        let line = line_at(pos.y).min(overview.line_count.saturating_sub(1));
+        resp.clone().on_hover_text(format!("Line {}", line + 1));
+    }
+    let jump = resp.clicked() || resp.dragged();
+    jump.then(|| resp.interact_pointer_pos()).flatten().map(|pos| line_at(pos.y).min(overview.line_count.saturating_sub(1)))
+}
@@ -1,23 +1,35 @@
+/// Case-insensitive (ASCII) substring matcher shared by the find bar and folder search.
+pub(crate) struct Matcher {
+    needle: String,
+}
+
+impl Matcher {
+    /// None for an empty query, which matches nothing.
+    pub(crate) fn new(query: &str) -> Option<Self> {
+        (!query.is_empty()).then(|| Self { needle: query.to_ascii_lowercase() })
+    }
+
+    /// Byte offsets of the non-overlapping matches in `text`.
+    pub(crate) fn find_iter(&self, text: &str) -> Vec<usize> {
+        // ASCII lowercasing keeps byte offsets unchanged
+        text.to_ascii_lowercase().match_indices(&self.needle).map(|(pos, _)| pos).collect()
+    }
+
+    pub(crate) fn is_match(&self, text: &str) -> bool {
+        text.to_ascii_lowercase().contains(&self.needle)
+    }
+}
+
 pub(crate) fn recompute_count(query: &str, text: &str) -> usize {
-    if query.is_empty() { return 0; }
-    let q = query.to_ascii_lowercase();
-    text.to_ascii_lowercase().matches(&q).count()
+    Matcher::new(query).map_or(0, |m| m.find_iter(text).len())
 }
 
 pub(crate) fn find_target_line(text: &str, query: &str, target_idx: usize) -> Option<usize> {
-    if query.is_empty() { return None; }
-    let q = query.to_ascii_lowercase();
+    let matcher = Matcher::new(query)?;
     let mut global = 0usize;
     for (i, line) in text.lines().enumerate() {
-        let mut rest = line.to_ascii_lowercase();
-        while let Some(pos) = rest.find(&q) {
-            if global == target_idx { return Some(i); }
-            global += 1;
-            let next = pos + q.len();
-            if next >= rest.len() { break; }
-            rest = rest[next..].to_string();
-        }
+        global += matcher.find_iter(line).len();
+        if global > target_idx { return Some(i); }
     }
     None
 }
-
@@ -0,0 +1,67 @@
+use eframe::egui;
+use std::path::PathBuf;
+
+/// What was open when the app last closed, restored on the next launch.
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct Session {
+    // Open files; the first entry is the one shown in the main view
+    pub(crate) files: Vec<PathBuf>,
+    pub(crate) window: WindowGeometry,
+    pub(crate) panels: PanelLayout,
+    pub(crate) search_query: String,
+    pub(crate) search_current: usize,
+}
+
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct WindowGeometry {
+    pub(crate) inner_size: Option<[f32; 2]>,
+    pub(crate) outer_pos: Option<[f32; 2]>,
+    pub(crate) maximized: bool,
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct PanelLayout {
+    pub(crate) search_bar: bool,
+    pub(crate) status_extra: bool,
+    pub(crate) metadata: bool,
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        Self { search_bar: true, status_extra: true, metadata: false }
+    }
+}
+
+impl WindowGeometry {
+    /// Record the current window geometry; sizes are skipped while maximized
+    /// so un-maximizing after a restore returns to the previous size.
+    pub(crate) fn capture(&mut self, ctx: &egui::Context) {
+        ctx.input(|i| {
+            let vp = i.viewport();
+            self.maximized = vp.maximized.unwrap_or(false);
+            if self.maximized || vp.fullscreen.unwrap_or(false) { return; }
+            if let Some(rect) = vp.inner_rect {
+                self.inner_size = Some([rect.width(), rect.height()]);
+            }
+            if let Some(rect) = vp.outer_rect {
+                self.outer_pos = Some([rect.min.x, rect.min.y]);
+            }
+        });
+    }
+
+    pub(crate) fn apply(&self, mut viewport: egui::ViewportBuilder) -> egui::ViewportBuilder {
+        if let Some([w, h]) = self.inner_size
+            && w >= 200.0
+            && h >= 150.0
+        {
+            viewport = viewport.with_inner_size([w, h]);
+        }
+        if let Some(pos) = self.outer_pos {
+            viewport = viewport.with_position(pos);
+        }
+        viewport.with_maximized(self.maximized)
+    }
+}
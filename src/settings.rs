@@ -12,12 +12,24 @@ pub(crate) fn load_settings_from_disk() -> Option<crate::app::FileViewerApp> {
     serde_json::from_slice::<crate::app::FileViewerApp>(&data).ok()
 }
 
+/// The app as saved: view preferences come from the global defaults rather
+/// than the open file's remembered state.
+pub(crate) fn to_settings_json(app: &crate::app::FileViewerApp) -> Option<serde_json::Value> {
+    let mut value = serde_json::to_value(app).ok()?;
+    let defaults = app.view_defaults;
+    value["text_zoom"] = defaults.text_zoom.into();
+    value["word_wrap"] = defaults.word_wrap.into();
+    value["image_zoom"] = defaults.image_zoom.into();
+    value["image_fit"] = defaults.image_fit.into();
+    Some(value)
+}
+
 pub(crate) fn save_settings_to_disk(app: &crate::app::FileViewerApp) {
     if let Some(path) = settings_path() {
         if let Some(parent) = path.parent() {
             let _ = fs::create_dir_all(parent);
         }
-        if let Ok(s) = serde_json::to_vec_pretty(app) {
+        if let Some(s) = to_settings_json(app).and_then(|v| serde_json::to_vec_pretty(&v).ok()) {
             let _ = fs::write(path, s);
         }
     }
@@ -0,0 +1,145 @@
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::app::{Content, FileViewerApp};
+
+pub(crate) const MIN_INTERVAL_SECS: f32 = 1.0;
+pub(crate) const MAX_INTERVAL_SECS: f32 = 60.0;
+
+/// A running slideshow. Interval, shuffle and loop are settings on the app so
+/// they persist; this only holds the playback state.
+pub(crate) struct Slideshow {
+    pub(crate) paused: bool,
+    elapsed: f32,
+    // Image the timer is running for; manual navigation restarts the timer
+    current: Option<PathBuf>,
+    // Shuffled play order and the position in it
+    order: Vec<PathBuf>,
+    position: usize,
+    was_fullscreen: bool,
+    rng: u64,
+}
+
+impl Slideshow {
+    // Shuffle `files`; `current` goes first when starting, last when looping
+    fn reshuffle(&mut self, files: &[PathBuf], current: &Path, starting: bool) {
+        self.order = files.to_vec();
+        for i in (1..self.order.len()).rev() {
+            let j = (self.next_random() % (i as u64 + 1)) as usize;
+            self.order.swap(i, j);
+        }
+        if let Some(index) = self.order.iter().position(|p| p == current) {
+            let target = if starting { 0 } else { self.order.len() - 1 };
+            self.order.swap(index, target);
+        }
+        self.position = 0;
+    }
+
+    // xorshift64; shuffling doesn't need a better generator
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+}
+
+/// Enter fullscreen and start cycling through the images in the current folder.
+pub(crate) fn start(app: &mut FileViewerApp, ctx: &egui::Context) {
+    if !matches!(app.content, Some(Content::Image(_))) { return; }
+    let Some(current) = app.current_path.clone() else { return };
+    let seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    let mut show = Slideshow {
+        paused: false,
+        elapsed: 0.0,
+        current: Some(current.clone()),
+        order: Vec::new(),
+        position: 0,
+        was_fullscreen: ctx.input(|i| i.viewport().fullscreen.unwrap_or(false)),
+        rng: seed | 1,
+    };
+    if app.slideshow_shuffle {
+        let files = crate::listing::siblings(app, &current).to_vec();
+        show.reshuffle(&files, &current, true);
+    }
+    ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+    app.gallery = None;
+    app.image_fit = true;
+    app.image_pan = egui::Vec2::ZERO;
+    app.slideshow = Some(show);
+}
+
+pub(crate) fn stop(app: &mut FileViewerApp, ctx: &egui::Context) {
+    if let Some(show) = app.slideshow.take()
+        && !show.was_fullscreen
+    {
+        ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(false));
+    }
+}
+
+/// Run the slideshow timer; returns the next image once the interval has passed.
+pub(crate) fn tick(app: &mut FileViewerApp, ctx: &egui::Context) -> Option<PathBuf> {
+    if !matches!(app.content, Some(Content::Image(_))) {
+        stop(app, ctx);
+        return None;
+    }
+    let interval = app.slideshow_interval.clamp(MIN_INTERVAL_SECS, MAX_INTERVAL_SECS);
+    let current = app.current_path.clone()?;
+    let show = app.slideshow.as_mut()?;
+    if show.current.as_ref() != Some(&current) {
+        show.current = Some(current.clone());
+        show.elapsed = 0.0;
+    }
+    if show.paused { return None; }
+    show.elapsed += ctx.input(|i| i.stable_dt).min(0.1);
+    if show.elapsed < interval {
+        ctx.request_repaint_after(Duration::from_secs_f32(interval - show.elapsed));
+        return None;
+    }
+    show.elapsed = 0.0;
+    let next = next_image(app, &current);
+    if next.is_none() {
+        stop(app, ctx);
+    }
+    next
+}
+
+fn next_image(app: &mut FileViewerApp, current: &Path) -> Option<PathBuf> {
+    let (shuffle, looping) = (app.slideshow_shuffle, app.slideshow_loop);
+    let files = crate::listing::siblings(app, current).to_vec();
+    let show = app.slideshow.as_mut()?;
+    if shuffle {
+        // Shuffle may have been switched on mid-show
+        if show.order.is_empty() {
+            show.reshuffle(&files, current, true);
+        }
+        show.position += 1;
+        if show.position >= show.order.len() {
+            if !looping { return None; }
+            show.reshuffle(&files, current, false);
+        }
+        return show.order.get(show.position).cloned();
+    }
+    let index = files.iter().position(|p| p == current)?;
+    if index + 1 == files.len() && !looping { return None; }
+    files.get((index + 1) % files.len()).cloned()
+}
+
+/// Hint drawn over the image while the slideshow is paused.
+pub(crate) fn paint_overlay(ui: &egui::Ui, app: &FileViewerApp) {
+    let Some(show) = &app.slideshow else { return };
+    if !show.paused { return; }
+    let rect = ui.max_rect();
+    let pos = egui::pos2(rect.center().x, rect.max.y - 32.0);
+    let painter = ui.painter();
+    let galley = painter.layout_no_wrap(
+        "Paused — Space to resume, ←/→ to browse, Esc to exit".to_string(),
+        egui::FontId::proportional(16.0),
+        egui::Color32::WHITE,
+    );
+    let text_rect = egui::Align2::CENTER_CENTER.anchor_size(pos, galley.size());
+    painter.rect_filled(text_rect.expand(8.0), 6.0, egui::Color32::from_black_alpha(160));
+    painter.galley(text_rect.min, galley, egui::Color32::WHITE);
+}
@@ -0,0 +1,178 @@
+use eframe::egui;
+use egui::{Color32, ColorImage, Pos2, Rect, TextureHandle};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use crate::io::FormatInfo;
+use crate::transform::ViewTransform;
+
+// Side of a full-resolution tile, in source pixels
+const TILE_SIZE: u32 = 1024;
+// Uploaded tiles kept before off-screen ones are dropped (~4 MB each)
+const MAX_CACHED_TILES: usize = 48;
+// Tiles uploaded per frame, so panning stays responsive while the rest stream in
+const UPLOADS_PER_FRAME: usize = 2;
+// Tiles requested from the worker but not received yet
+const MAX_PENDING_TILES: usize = 4;
+
+/// Full-resolution tiles of a downsampled image, drawn over the preview as
+/// they come into view. The file is decoded again on a worker thread the
+/// first time tiles are needed; the worker holds the pixels until released.
+pub(crate) struct TileSource {
+    path: PathBuf,
+    info: &'static FormatInfo,
+    size: [u32; 2],
+    worker: Option<TileWorker>,
+    // Why the full-resolution decode failed, e.g. over the memory cap
+    error: Option<String>,
+    pending: HashSet<[u32; 2]>,
+    cache: HashMap<[u32; 2], TextureHandle>,
+}
+
+struct TileWorker {
+    requests: Sender<[u32; 2]>,
+    tiles: Receiver<Result<([u32; 2], ColorImage), String>>,
+}
+
+impl TileSource {
+    pub(crate) fn new(path: PathBuf, info: &'static FormatInfo, size: [usize; 2]) -> Self {
+        Self {
+            path,
+            info,
+            size: [size[0] as u32, size[1] as u32],
+            worker: None,
+            error: None,
+            pending: HashSet::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn cached(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub(crate) fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Drop uploaded tiles, e.g. after the filter mode changed.
+    pub(crate) fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Stop the worker and free the full-resolution pixels; they are decoded
+    /// again if tiles are painted later.
+    pub(crate) fn release(&mut self) {
+        self.worker = None;
+        self.error = None;
+        self.pending.clear();
+        self.cache.clear();
+    }
+
+    fn spawn_worker(&mut self, ctx: &egui::Context) {
+        let (request_tx, request_rx) = mpsc::channel::<[u32; 2]>();
+        let (tile_tx, tile_rx) = mpsc::channel();
+        let (path, info, ctx) = (self.path.clone(), self.info, ctx.clone());
+        std::thread::spawn(move || {
+            let image = match crate::io::decode_for_tiles(&path, info) {
+                Ok(image) => image,
+                Err(e) => {
+                    let _ = tile_tx.send(Err(e));
+                    ctx.request_repaint();
+                    return;
+                }
+            };
+            let [w, h] = [image.width(), image.height()];
+            // Ends when the source is released or dropped
+            while let Ok([tx, ty]) = request_rx.recv() {
+                let (x0, y0) = (tx * TILE_SIZE, ty * TILE_SIZE);
+                let (x1, y1) = ((x0 + TILE_SIZE).min(w), (y0 + TILE_SIZE).min(h));
+                if tile_tx.send(Ok(([tx, ty], crate::io::region_pixels(&image, x0, y0, x1 - x0, y1 - y0)))).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+        });
+        self.worker = Some(TileWorker { requests: request_tx, tiles: tile_rx });
+    }
+
+    // Upload tiles the worker finished, a few per frame
+    fn receive(&mut self, ctx: &egui::Context, options: egui::TextureOptions) {
+        let Some(worker) = &self.worker else { return };
+        for _ in 0..UPLOADS_PER_FRAME {
+            match worker.tiles.try_recv() {
+                Ok(Ok((key, pixels))) => {
+                    self.pending.remove(&key);
+                    self.cache.insert(key, ctx.load_texture(format!("tile-{}-{}", key[0], key[1]), pixels, options));
+                }
+                Ok(Err(e)) => {
+                    self.release();
+                    self.error = Some(e);
+                    return;
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.release();
+                    self.error = Some("Tile decoder stopped".to_string());
+                    return;
+                }
+            }
+        }
+        ctx.request_repaint();
+    }
+
+    /// Paint the tiles covering the visible part of `image_rect`, requesting missing ones.
+    pub(crate) fn paint(&mut self, ctx: &egui::Context, painter: &egui::Painter, image_rect: Rect, transform: ViewTransform, options: egui::TextureOptions) {
+        if self.error.is_some() {
+            return;
+        }
+        if self.worker.is_none() {
+            self.spawn_worker(ctx);
+        }
+        self.receive(ctx, options);
+        let [w, h] = self.size;
+        let visible = image_rect.intersect(painter.clip_rect());
+        let view = if transform.quarter_turns % 2 == 1 { [h as f32, w as f32] } else { [w as f32, h as f32] };
+        // Source pixel coordinates -> screen, through the view rotation/flip
+        let to_screen = |x: u32, y: u32| {
+            let [vx, vy] = transform.map_point([x as f32, y as f32], [w as f32, h as f32]);
+            Pos2::new(
+                image_rect.min.x + vx / view[0] * image_rect.width(),
+                image_rect.min.y + vy / view[1] * image_rect.height(),
+            )
+        };
+
+        let mut shown = Vec::new();
+        for ty in 0..h.div_ceil(TILE_SIZE) {
+            for tx in 0..w.div_ceil(TILE_SIZE) {
+                let (x0, y0) = (tx * TILE_SIZE, ty * TILE_SIZE);
+                let (x1, y1) = ((x0 + TILE_SIZE).min(w), (y0 + TILE_SIZE).min(h));
+                let corners = [to_screen(x0, y0), to_screen(x1, y0), to_screen(x1, y1), to_screen(x0, y1)];
+                if !Rect::from_points(&corners).intersects(visible) { continue; }
+                let key = [tx, ty];
+                let Some(texture) = self.cache.get(&key) else {
+                    if !self.pending.contains(&key)
+                        && self.pending.len() < MAX_PENDING_TILES
+                        && let Some(worker) = &self.worker
+                        && worker.requests.send(key).is_ok()
+                    {
+                        self.pending.insert(key);
+                    }
+                    continue;
+                };
+                let mut mesh = egui::Mesh::with_texture(texture.id());
+                let uvs = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+                for (pos, (u, v)) in corners.into_iter().zip(uvs) {
+                    mesh.vertices.push(egui::epaint::Vertex { pos, uv: Pos2::new(u, v), color: Color32::WHITE });
+                }
+                mesh.add_triangle(0, 1, 2);
+                mesh.add_triangle(0, 2, 3);
+                painter.add(mesh);
+                shown.push(key);
+            }
+        }
+        if self.cache.len() > MAX_CACHED_TILES {
+            self.cache.retain(|key, _| shown.contains(key));
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use eframe::egui::ColorImage;
+use image::metadata::Orientation;
+
+/// A view-only rotate/flip command.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TransformOp {
+    RotateCw,
+    RotateCcw,
+    Rotate180,
+    FlipHorizontal,
+    FlipVertical,
+}
+
+/// Accumulated view transform: flip horizontally (if `flipped`), then rotate
+/// clockwise by `quarter_turns`. Every combination of rotations and flips
+/// reduces to one of these eight states.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ViewTransform {
+    pub(crate) quarter_turns: u8,
+    pub(crate) flipped: bool,
+}
+
+impl ViewTransform {
+    pub(crate) fn is_identity(self) -> bool {
+        self == Self::default()
+    }
+
+    pub(crate) fn then(self, op: TransformOp) -> Self {
+        let k = self.quarter_turns;
+        let (quarter_turns, flipped) = match op {
+            TransformOp::RotateCw => (k + 1, self.flipped),
+            TransformOp::RotateCcw => (k + 3, self.flipped),
+            TransformOp::Rotate180 => (k + 2, self.flipped),
+            // F·R^k = R^-k·F, and a vertical flip is R^2·F
+            TransformOp::FlipHorizontal => (4 - k, !self.flipped),
+            TransformOp::FlipVertical => (6 - k, !self.flipped),
+        };
+        Self { quarter_turns: quarter_turns % 4, flipped }
+    }
+
+    pub(crate) fn to_orientation(self) -> Orientation {
+        match (self.quarter_turns, self.flipped) {
+            (1, false) => Orientation::Rotate90,
+            (2, false) => Orientation::Rotate180,
+            (3, false) => Orientation::Rotate270,
+            (0, true) => Orientation::FlipHorizontal,
+            (1, true) => Orientation::Rotate270FlipH,
+            (2, true) => Orientation::FlipVertical,
+            (3, true) => Orientation::Rotate90FlipH,
+            _ => Orientation::NoTransforms,
+        }
+    }
+
+    /// Map a point in source image coordinates (`size` is the source width
+    /// and height) to where it lands in the transformed view.
+    pub(crate) fn map_point(self, [x, y]: [f32; 2], [w, h]: [f32; 2]) -> [f32; 2] {
+        let (mut x, mut y, mut w, mut h) = (x, y, w, h);
+        if self.flipped {
+            x = w - x;
+        }
+        for _ in 0..self.quarter_turns {
+            // Clockwise quarter turn: (x, y) -> (h - y, x), and the sides swap
+            (x, y, w, h) = (h - y, x, h, w);
+        }
+        [x, y]
+    }
+
+    pub(crate) fn label(self) -> String {
+        let mut parts = Vec::new();
+        if self.quarter_turns != 0 {
+            parts.push(format!("{}°", self.quarter_turns as u32 * 90));
+        }
+        if self.flipped {
+            parts.push("flipped".to_string());
+        }
+        parts.join(" ")
+    }
+}
+
+/// Apply an accumulated view transform to freshly decoded pixels.
+pub(crate) fn apply_view(image: ColorImage, transform: ViewTransform) -> ColorImage {
+    let mut image = image;
+    if transform.flipped {
+        image = apply(&image, TransformOp::FlipHorizontal);
+    }
+    for _ in 0..transform.quarter_turns {
+        image = apply(&image, TransformOp::RotateCw);
+    }
+    image
+}
+
+/// Apply a single rotate/flip command to decoded pixels.
+pub(crate) fn apply(image: &ColorImage, op: TransformOp) -> ColorImage {
+    let (size, pixels) = remap(&image.pixels, image.size, op);
+    ColorImage { size, pixels }
+}
+
+/// Apply a rotate/flip command to a row-major `size` grid; returns the new size and grid.
+pub(crate) fn remap<T: Copy>(grid: &[T], size: [usize; 2], op: TransformOp) -> ([usize; 2], Vec<T>) {
+    let [w, h] = size;
+    let size = match op {
+        TransformOp::RotateCw | TransformOp::RotateCcw => [h, w],
+        _ => [w, h],
+    };
+    let mut pixels = Vec::with_capacity(w * h);
+    for y in 0..size[1] {
+        for x in 0..size[0] {
+            // Index of the source pixel that lands on destination (x, y)
+            let src = match op {
+                TransformOp::RotateCw => (h - 1 - x) * w + y,
+                TransformOp::RotateCcw => x * w + (w - 1 - y),
+                TransformOp::Rotate180 => (h - 1 - y) * w + (w - 1 - x),
+                TransformOp::FlipHorizontal => y * w + (w - 1 - x),
+                TransformOp::FlipVertical => (h - 1 - y) * w + x,
+            };
+            pixels.push(grid[src]);
+        }
+    }
+    (size, pixels)
+}
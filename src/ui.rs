@@ -3,21 +3,26 @@ use eframe::egui;
 
 pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, ctx: &egui::Context, file_to_load: &mut Option<PathBuf>) {
     
-    use rfd::FileDialog;
     use egui::RichText;
 
     if ui
         .button(RichText::new("Open File"))
         .clicked()
-        && let Some(path) = FileDialog::new()
-            .add_filter("All Supported", &["txt","rs","py","toml","md","json","js","html","css","png","jpg","jpeg","gif","bmp","webp"])
-            .add_filter("Images", &["png","jpg","jpeg","gif","bmp","webp"])
-            .add_filter("Text/Source", &["txt","rs","py","toml","md","json","js","html","css"])
-            .pick_file()
+        && let Some(path) = crate::io::open_file_dialog()
     {
         *file_to_load = Some(path);
     }
 
+    let gallery_label = if app.gallery.is_some() { "Close Gallery" } else { "Gallery" };
+    if ui.button(gallery_label).on_hover_text("Thumbnails of the images in the current folder (G)").clicked() {
+        crate::gallery::toggle(app, ctx);
+    }
+
+    let folder_label = if app.folder_search.is_some() { "Close Folder Search" } else { "Find in Folder" };
+    if ui.button(folder_label).on_hover_text("Search every file under a folder (Ctrl+Shift+F)").clicked() {
+        crate::folder_search::toggle(app);
+    }
+
     ui.menu_button(RichText::new("Recent Files"), |ui| {
         ui.set_min_width(480.0);
         ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
@@ -38,10 +43,43 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, ct
         ui.separator();
         if ui.button("Clear Recent Files").clicked() {
             app.recent_files.clear();
+            app.view_states.clear();
             ui.close_menu();
         }
     });
 
+    ui.menu_button(RichText::new("View"), |ui| {
+        ui.checkbox(&mut app.show_search_bar, "Find Bar");
+        ui.checkbox(&mut app.show_status_extra, "Status Details");
+        ui.checkbox(&mut app.show_metadata, "Image Metadata");
+        // Turning tiles off frees the full-resolution pixels held for them
+        if ui.checkbox(&mut app.full_res_tiles, "Full-Resolution Tiles").changed()
+            && !app.full_res_tiles
+            && let Some(crate::app::Content::Image(image)) = &mut app.content
+            && let Some(tiles) = &mut image.tiles
+        {
+            tiles.release();
+        }
+        ui.separator();
+        ui.menu_button("Prev/Next Order", |ui| {
+            for sort in crate::listing::NeighborSort::ALL {
+                ui.radio_value(&mut app.neighbor_sort, sort, sort.label());
+            }
+            ui.separator();
+            ui.checkbox(&mut app.neighbor_recursive, "Include Subfolders");
+        });
+        ui.separator();
+        let prev_restore = app.restore_session;
+        ui.checkbox(&mut app.restore_session, "Restore Session on Startup");
+        if app.restore_session != prev_restore {
+            app.session_enabled = app.restore_session;
+            if !app.restore_session {
+                app.session = crate::session::Session::default();
+            }
+            crate::settings::save_settings_to_disk(app);
+        }
+    });
+
     ui.separator();
     let prev_dark = app.dark_mode;
     let prev_lines = app.show_line_numbers;
@@ -56,32 +94,149 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, ct
     ui.separator();
 
     if ui.button("Clear").clicked() {
-        app.content = None;
-        app.current_path = None;
-        app.error_message = None;
+        app.clear();
     }
 
+    if app.content.is_some()
+        && let Some(cur) = app.current_path.clone()
+    {
+        ui.separator();
+        if ui.button("Prev").on_hover_text("Previous file in folder (←)").clicked()
+            && let Some(prev) = crate::listing::neighbor(app, &cur, false)
+        {
+            *file_to_load = Some(prev);
+        }
+        if ui.button("Next").on_hover_text("Next file in folder (→)").clicked()
+            && let Some(next) = crate::listing::neighbor(app, &cur, true)
+        {
+            *file_to_load = Some(next);
+        }
+    }
+
+    if matches!(app.content, Some(crate::app::Content::Text(_))) && app.current_path.is_some() {
+        ui.separator();
+        if ui.button("Compare with…").on_hover_text("Diff this file against another").clicked() {
+            crate::diff_view::compare_with(app);
+        }
+        let has_head = app.git_status.as_ref().is_some_and(|s| s.head_text.is_some());
+        if ui.add_enabled(has_head, egui::Button::new("View at HEAD")).on_hover_text("Diff the committed version against this file (Ctrl+G)").clicked() {
+            crate::diff_view::open_head(app);
+        }
+        let mut log_mode = app.log.is_some();
+        if ui.checkbox(&mut log_mode, "Log View").on_hover_text("Color lines by level and filter them").changed() {
+            app.log = match (&app.content, log_mode) {
+                (Some(crate::app::Content::Text(text)), true) => Some(crate::log_view::LogView::new(text)),
+                _ => None,
+            };
+            // The ruler's error lane includes log errors
+            app.overview = None;
+        }
+        if app.fold_regions.is_some() {
+            if ui.button("Fold All").on_hover_text("Ctrl+Shift+[").clicked() { app.fold_all(true); }
+            if ui.button("Unfold All").on_hover_text("Ctrl+Shift+]").clicked() { app.fold_all(false); }
+        }
+        if ui.add_enabled(app.outline.is_some(), egui::Checkbox::new(&mut app.show_outline, "Outline")).on_hover_text("List definitions and headings (Ctrl+Shift+O)").changed() {
+            crate::settings::save_settings_to_disk(app);
+        }
+        let mut ruler_changed = ui.checkbox(&mut app.show_overview, "Overview").on_hover_text("Ruler marking matches, bookmarks, git changes and errors").changed();
+        ruler_changed |= ui.add_enabled(app.show_overview, egui::Checkbox::new(&mut app.show_minimap, "Minimap")).changed();
+        if ruler_changed {
+            crate::settings::save_settings_to_disk(app);
+        }
+        let in_repo = app.git_status.is_some();
+        if ui.add_enabled(in_repo, egui::Checkbox::new(&mut app.show_blame, "Blame")).on_hover_text("Show who last changed each line").changed() {
+            if app.show_blame
+                && let (Some(path), Some(crate::app::Content::Text(text))) = (&app.current_path, &app.content)
+                && !app.text_is_big
+            {
+                app.blame.request(ctx, path, text);
+            }
+            crate::settings::save_settings_to_disk(app);
+        }
+        if app.show_blame && app.current_path.as_deref().is_some_and(|p| app.blame.is_pending(p)) {
+            ui.spinner();
+        }
+    }
+
     if matches!(app.content, Some(crate::app::Content::Image(_))) {
         ui.separator();
         let prev_fit = app.image_fit;
-        if let Some(cur) = app.current_path.clone() {
-            if ui.button("Prev").clicked() {
-                if let Some(prev) = crate::io::neighbor_image(&cur, false) {
-                    *file_to_load = Some(prev);
+        if app.current_path.is_some() {
+            ui.menu_button("Slideshow", |ui| {
+                if ui.button("Start (F5)").clicked() {
+                    crate::slideshow::start(app, ctx);
+                    ui.close_menu();
                 }
+                let range = crate::slideshow::MIN_INTERVAL_SECS..=crate::slideshow::MAX_INTERVAL_SECS;
+                ui.add(egui::Slider::new(&mut app.slideshow_interval, range).suffix(" s").text("Interval"));
+                ui.checkbox(&mut app.slideshow_shuffle, "Shuffle");
+                ui.checkbox(&mut app.slideshow_loop, "Loop");
+            });
+        }
+        ui.separator();
+        ui.checkbox(&mut app.image_fit, "Fit to Window");
+        if app.image_fit != prev_fit {
+            app.view_defaults.image_fit = app.image_fit;
+            crate::settings::save_settings_to_disk(app);
+        }
+        if ui.button("Zoom -").clicked() { crate::image_view::zoom_by(app, 1.0 / crate::image_view::ZOOM_STEP, None); }
+        if ui.button("Zoom +").clicked() { crate::image_view::zoom_by(app, crate::image_view::ZOOM_STEP, None); }
+        if ui.button("100%").clicked() { crate::image_view::set_zoom(app, 1.0, None); }
+        ui.separator();
+        {
+            use crate::transform::TransformOp;
+            if ui.button("⟲").on_hover_text("Rotate left (Shift+R)").clicked() { crate::image_view::transform_image(app, TransformOp::RotateCcw); }
+            if ui.button("⟳").on_hover_text("Rotate right (R)").clicked() { crate::image_view::transform_image(app, TransformOp::RotateCw); }
+            if ui.button("180°").on_hover_text("Rotate 180°").clicked() { crate::image_view::transform_image(app, TransformOp::Rotate180); }
+            if ui.button("⇔").on_hover_text("Flip horizontally (H)").clicked() { crate::image_view::transform_image(app, TransformOp::FlipHorizontal); }
+            if ui.button("⇕").on_hover_text("Flip vertically (V)").clicked() { crate::image_view::transform_image(app, TransformOp::FlipVertical); }
+            let transformed = matches!(&app.content, Some(crate::app::Content::Image(image)) if !image.transform.is_identity());
+            if ui.add_enabled(transformed, egui::Button::new("Save Rotated Copy…")).clicked() {
+                crate::image_view::save_transformed_copy(app);
             }
-            if ui.button("Next").clicked() {
-                if let Some(next) = crate::io::neighbor_image(&cur, true) {
-                    *file_to_load = Some(next);
+        }
+        let animation = match &app.content {
+            Some(crate::app::Content::Image(image)) if image.is_animated() => Some(image.playing),
+            _ => None,
+        };
+        if let Some(playing) = animation {
+            ui.separator();
+            if ui.button("◀|").on_hover_text("Previous frame (,)").clicked() { crate::image_view::step_frame(app, -1); }
+            if ui.button(if playing { "Pause" } else { "Play" }).on_hover_text("Space").clicked() { crate::image_view::toggle_playback(app); }
+            if ui.button("|▶").on_hover_text("Next frame (.)").clicked() { crate::image_view::step_frame(app, 1); }
+        }
+        ui.separator();
+        let prev_filter = app.image_filter;
+        egui::ComboBox::from_label("Filter")
+            .selected_text(app.image_filter.label())
+            .show_ui(ui, |ui| {
+                for filter in crate::image_view::ImageFilter::ALL {
+                    ui.selectable_value(&mut app.image_filter, filter, filter.label());
                 }
-            }
+            });
+        if app.image_filter != prev_filter {
+            crate::image_view::apply_filter(app);
+            crate::settings::save_settings_to_disk(app);
+        }
+        let prev_bg = app.transparency_bg;
+        egui::ComboBox::from_label("Background")
+            .selected_text(app.transparency_bg.label())
+            .show_ui(ui, |ui| {
+                for bg in crate::image_view::TransparencyBg::ALL {
+                    ui.selectable_value(&mut app.transparency_bg, bg, bg.label());
+                }
+            });
+        if app.transparency_bg == crate::image_view::TransparencyBg::Custom {
+            ui.color_edit_button_srgb(&mut app.transparency_custom);
         }
+        if app.transparency_bg != prev_bg { crate::settings::save_settings_to_disk(app); }
         ui.separator();
-        ui.checkbox(&mut app.image_fit, "Fit to Window");
-        if app.image_fit != prev_fit { crate::settings::save_settings_to_disk(app); }
-        if ui.button("Zoom -").clicked() { app.image_fit = false; app.image_zoom = (app.image_zoom / 1.10).clamp(0.1, 6.0); }
-        if ui.button("Zoom +").clicked() { app.image_fit = false; app.image_zoom = (app.image_zoom * 1.10).clamp(0.1, 6.0); }
-        if ui.button("100%").clicked() { app.image_fit = false; app.image_zoom = 1.0; }
+        ui.checkbox(&mut app.show_metadata, "Metadata").on_hover_text("Show EXIF, ICC and format details (I)");
+        ui.checkbox(&mut app.show_loupe, "Loupe").on_hover_text("Magnify the pixels under the cursor");
+        ui.separator();
+        if ui.button("Compare…").on_hover_text("Compare with another image").clicked() {
+            crate::compare::pick_and_open(app, ctx);
+        }
     }
 }
 
@@ -94,6 +249,10 @@ pub(crate) fn search_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp)
             resp.request_focus();
             app.search_active = false;
         }
+        // Escape closes the find bar; Ctrl+F brings it back
+        if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            app.show_search_bar = false;
+        }
         // Enter / Shift+Enter navigate matches
         let (enter, shift) = ui.input(|i| (i.key_pressed(egui::Key::Enter), i.modifiers.shift));
         if enter && app.search_count > 0 {
@@ -107,10 +266,11 @@ pub(crate) fn search_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp)
         if resp.changed() || (prev.is_empty() && !app.search_query.is_empty()) {
             app.search_count = 0;
             app.search_current = 0;
-            if let Some(crate::app::Content::Text(ref text)) = app.content {
-                if !app.search_query.is_empty() && text.len() <= crate::app::HIGHLIGHT_CHAR_THRESHOLD {
-                    app.search_count = crate::search::recompute_count(&app.search_query, text);
-                }
+            if let Some(crate::app::Content::Text(ref text)) = app.content
+                && !app.search_query.is_empty()
+                && text.len() <= crate::app::HIGHLIGHT_CHAR_THRESHOLD
+            {
+                app.search_count = crate::search::recompute_count(&app.search_query, text);
             }
         }
         if !app.search_query.is_empty() {
@@ -126,17 +286,46 @@ pub(crate) fn search_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp)
                 ui.label(format!("{}/{}", app.search_current + 1, app.search_count));
             }
         }
+        ui.separator();
+        ui.checkbox(&mut app.filter_lines, "Filter Lines").on_hover_text("Show only the lines that match");
+        if app.filter_lines {
+            ui.add(egui::DragValue::new(&mut app.filter_context).range(0..=crate::line_filter::MAX_CONTEXT).prefix("Context: "));
+            ui.checkbox(&mut app.filter_invert, "Invert").on_hover_text("Show the lines that don't match, like grep -v");
+            if let Some(filter) = &app.line_filter
+                && crate::line_filter::is_active(app)
+            {
+                ui.label(format!("{} line(s)", filter.hits));
+            }
+        }
     });
 }
 
 pub(crate) fn status_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
     use std::fs;
     ui.horizontal(|ui| {
-        if let Some(path) = &app.current_path {
+        if let Some(diff) = &app.text_diff {
+            ui.monospace(format!("{}  ↔  {}", diff.left_path.display(), diff.right_path.display()));
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                match diff.changes.len() {
+                    0 => ui.label("No differences"),
+                    count => ui.label(format!("Change {} of {}", diff.current + 1, count)),
+                };
+            });
+        } else if let Some(path) = &app.current_path {
             ui.monospace(path.to_string_lossy());
-            if let Ok(metadata) = fs::metadata(path) {
+            let size = fs::metadata(path).ok().map(|m| m.len());
+            let branch = app.git_status.as_ref().map(|s| s.branch.as_str());
+            if size.is_some() || branch.is_some() {
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    ui.label(format!("({:.1} KB)", metadata.len() as f64 / 1024.0));
+                    if let Some(len) = size {
+                        ui.label(format!("({:.1} KB)", len as f64 / 1024.0));
+                    }
+                    if let Some(branch) = branch {
+                        ui.label(format!("⎇ {}", branch)).on_hover_text("Git branch");
+                    }
+                    if let Some(log) = &app.log {
+                        crate::log_view::level_counts(ui, log);
+                    }
                 });
             }
             if ui.button("Copy Path").on_hover_text("Copy path to clipboard").clicked() {
@@ -159,23 +348,163 @@ pub(crate) fn status_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp)
 pub(crate) fn status_extra(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
     ui.horizontal(|ui| {
         match &app.content {
-            Some(crate::app::Content::Image(texture)) => {
-                let size = texture.size();
-                ui.label(format!("Image: {}x{} px", size[0], size[1]));
-                let eff = if app.image_fit { None } else { Some(app.image_zoom) };
-                if let Some(z) = eff { ui.label(format!("Zoom: {:.0}%", z * 100.0)); }
-                let est = (size[0] as usize).saturating_mul(size[1] as usize).saturating_mul(4);
+            Some(crate::app::Content::Image(image)) => {
+                let size = image.texture.size();
+                if image.svg.is_some() {
+                    let logical = image.display_size();
+                    ui.label(format!("SVG: {:.0}x{:.0} (raster {}x{} px)", logical.x, logical.y, size[0], size[1]));
+                } else if image.full_size.is_some() {
+                    let full = image.display_size();
+                    ui.label(format!("Image: {:.0}x{:.0} px (preview {}x{})", full.x, full.y, size[0], size[1]));
+                    if let Some(tiles) = &image.tiles {
+                        match tiles.error() {
+                            Some(e) => { ui.colored_label(egui::Color32::LIGHT_RED, format!("Tiles unavailable: {}", e)); }
+                            None => { ui.label(format!("Tiles: {}", tiles.cached())); }
+                        }
+                    }
+                } else {
+                    ui.label(format!("Image: {}x{} px", size[0], size[1]));
+                }
+                let zoom = if app.image_fit { app.image_zoom_shown } else { app.image_zoom };
+                ui.label(format!("Zoom: {:.0}%", zoom * 100.0));
+                let est = size[0].saturating_mul(size[1]).saturating_mul(4);
                 ui.label(format!("Texture ~{:.1} MB", est as f64 / (1024.0 * 1024.0)));
                 if app.image_fit { ui.label("Fit: on"); }
+                if !image.transform.is_identity() {
+                    ui.label(format!("View: {}", image.transform.label()));
+                }
+                if image.is_animated() {
+                    let delay = image.frames[image.current_frame].delay;
+                    ui.label(format!("Frame {}/{} ({} ms)", image.current_frame + 1, image.frames.len(), delay.as_millis()));
+                    if !image.playing { ui.label("Paused"); }
+                    if image.frames_truncated { ui.label("Frames truncated (memory limit)"); }
+                }
+                if let Some(px) = app.hovered_pixel {
+                    ui.separator();
+                    let (swatch, _) = ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::hover());
+                    ui.painter().rect_filled(swatch, 2.0, px.color());
+                    let [r, g, b, a] = px.rgba;
+                    ui.monospace(format!("({}, {})  RGBA({}, {}, {}, {})  {}", px.x, px.y, r, g, b, a, px.hex()));
+                }
+                if let Some(hex) = &app.copied_color {
+                    ui.label(format!("Copied {}", hex));
+                }
             }
-            Some(crate::app::Content::Text(_)) => {
+            Some(crate::app::Content::Text(text)) => {
                 ui.label(format!("Lines: {}", app.text_line_count));
                 ui.label(format!("Zoom: {:.0}%", app.text_zoom * 100.0));
                 if app.text_is_big { ui.label("Large file: reduced features"); }
                 if app.text_is_lossy { ui.label("UTF-8 (lossy)"); }
+                if let Some((line, byte)) = app.text_cursor {
+                    let column = text.lines().nth(line).and_then(|l| l.get(..byte)).map_or(0, |l| l.chars().count());
+                    ui.label(format!("Ln {}, Col {}", line + 1, column + 1));
+                }
+                let unbalanced = app.brackets.as_ref().map_or(0, |b| b.unbalanced_count());
+                if unbalanced > 0 {
+                    ui.colored_label(crate::app::UNBALANCED_BRACKET_COLOR, format!("{} unbalanced bracket{}", unbalanced, if unbalanced == 1 { "" } else { "s" }));
+                }
             }
             _ => {}
         }
     });
 }
 
+
+pub(crate) fn diff_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    let mut close = false;
+    ui.horizontal_wrapped(|ui| {
+        let Some(diff) = &mut app.text_diff else { return };
+        let name = |p: &std::path::Path| p.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        ui.label("Diff:");
+        ui.monospace(name(&diff.left_path)).on_hover_text(diff.left_path.to_string_lossy());
+        ui.label("↔");
+        ui.monospace(name(&diff.right_path)).on_hover_text(diff.right_path.to_string_lossy());
+        ui.separator();
+        ui.selectable_value(&mut diff.inline, false, "Side by Side");
+        ui.selectable_value(&mut diff.inline, true, "Inline");
+        ui.separator();
+        if ui.button("Prev Change").on_hover_text("Alt+↑").clicked() { diff.step(false); }
+        if ui.button("Next Change").on_hover_text("Alt+↓").clicked() { diff.step(true); }
+        ui.separator();
+        close = ui.button("Close").on_hover_text("Esc").clicked();
+    });
+    if close {
+        app.text_diff = None;
+    }
+}
+
+pub(crate) fn compare_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    use crate::compare::CompareMode;
+    if app.compare.as_ref().is_some_and(|c| c.mode == CompareMode::Difference) {
+        crate::compare::ensure_diff(app, ui.ctx());
+    }
+    let mut change = false;
+    let mut close = false;
+    ui.horizontal_wrapped(|ui| {
+        let Some(compare) = &mut app.compare else { return };
+        ui.label("Compare with:");
+        let name = compare.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        ui.monospace(name).on_hover_text(compare.path.to_string_lossy());
+        change = ui.button("Change…").clicked();
+        ui.separator();
+        for mode in CompareMode::ALL {
+            ui.selectable_value(&mut compare.mode, mode, mode.label());
+        }
+        ui.separator();
+        match compare.mode {
+            CompareMode::SideBySide => {}
+            CompareMode::Swipe => { ui.add(egui::Slider::new(&mut compare.swipe, 0.0..=1.0).show_value(false).text("Position")); }
+            CompareMode::Onion => { ui.add(egui::Slider::new(&mut compare.opacity, 0.0..=1.0).text("Opacity")); }
+            CompareMode::Difference => { ui.add(egui::Slider::new(&mut compare.tolerance, 0..=255).text("Tolerance")); }
+        }
+        close = ui.button("Close").clicked();
+    });
+    if let Some(stats) = crate::compare::diff_stats(app)
+        && app.compare.as_ref().is_some_and(|c| c.mode == CompareMode::Difference)
+    {
+        ui.horizontal_wrapped(|ui| {
+            if stats.mismatched == 0 {
+                ui.label("Identical");
+            } else {
+                let percent = stats.mismatched as f64 * 100.0 / stats.total.max(1) as f64;
+                let unit = if stats.downsampled { "preview pixels" } else { "pixels" };
+                ui.label(format!("{} {} differ ({:.3}%)", stats.mismatched, unit, percent));
+            }
+            if let Some([x0, y0, x1, y1]) = stats.bounds {
+                ui.label(format!("Bounds: ({}, {}) – ({}, {}), {}x{}", x0, y0, x1, y1, x1 - x0, y1 - y0));
+            }
+            if stats.size_differs { ui.label("Sizes differ"); }
+        });
+    }
+    if change {
+        crate::compare::pick_and_open(app, ui.ctx());
+    }
+    if close {
+        app.compare = None;
+    }
+}
+
+pub(crate) fn metadata_panel(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    let path = app.current_path.clone();
+    let Some(crate::app::Content::Image(image)) = &mut app.content else { return };
+    let meta = image.metadata.get_or_insert_with(|| {
+        path.as_deref().map(crate::metadata::read_metadata).unwrap_or_default()
+    });
+    ui.heading("Metadata");
+    ui.separator();
+    egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        for section in &meta.sections {
+            egui::CollapsingHeader::new(&section.title)
+                .default_open(true)
+                .show(ui, |ui| {
+                    egui::Grid::new(&section.title).num_columns(2).striped(true).show(ui, |ui| {
+                        for (key, value) in &section.rows {
+                            ui.label(egui::RichText::new(key).strong());
+                            ui.add(egui::Label::new(value).wrap());
+                            ui.end_row();
+                        }
+                    });
+                });
+        }
+    });
+}
@@ -0,0 +1,64 @@
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+// Entries whose file mtime moved by more than this are considered stale
+const MTIME_TOLERANCE_SECS: u64 = 2;
+
+/// Per-file view state remembered for entries in the recent files list.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct ViewState {
+    pub(crate) scroll_offset: [f32; 2],
+    pub(crate) search_query: String,
+    pub(crate) text_zoom: f32,
+    pub(crate) word_wrap: bool,
+    pub(crate) image_zoom: f32,
+    pub(crate) image_fit: bool,
+    pub(crate) mtime_secs: u64,
+}
+
+impl Default for ViewState {
+    fn default() -> Self {
+        Self {
+            scroll_offset: [0.0, 0.0],
+            search_query: String::new(),
+            text_zoom: 1.0,
+            word_wrap: true,
+            image_zoom: 1.0,
+            image_fit: false,
+            mtime_secs: 0,
+        }
+    }
+}
+
+impl ViewState {
+    /// True when the file changed on disk since this state was captured.
+    pub(crate) fn is_stale(&self, path: &Path) -> bool {
+        match file_mtime_secs(path) {
+            Some(now) => now.abs_diff(self.mtime_secs) > MTIME_TOLERANCE_SECS,
+            None => true,
+        }
+    }
+}
+
+/// Global view preferences: what files without remembered state open with,
+/// and what is saved as the settings while a file's own state is shown.
+#[derive(Clone, Copy)]
+pub(crate) struct ViewDefaults {
+    pub(crate) text_zoom: f32,
+    pub(crate) word_wrap: bool,
+    pub(crate) image_zoom: f32,
+    pub(crate) image_fit: bool,
+}
+
+impl Default for ViewDefaults {
+    fn default() -> Self {
+        Self { text_zoom: 1.0, word_wrap: true, image_zoom: 1.0, image_fit: false }
+    }
+}
+
+pub(crate) fn file_mtime_secs(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    modified.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}